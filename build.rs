@@ -1,26 +1,18 @@
 use std::env;
-use std::fs;
 use std::path::PathBuf;
 
-fn is_wsl() -> bool {
-  std::fs::read_to_string("/proc/version")
-    .map(|s| s.to_lowercase().contains("microsoft"))
-    .unwrap_or(false)
-}
+// The lookup `ssq` reads the base config with, so it's installed there
+#[path = "src/paths.rs"]
+mod paths;
 
-fn get_config_dir() -> Option<PathBuf> {
-  if cfg!(windows) && !is_wsl() {
-    env::var("APPDATA")
-      .ok()
-      .map(|appdata| PathBuf::from(appdata).join("secret-squirrel"))
-  } else {
-    env::var("HOME")
-      .ok()
-      .map(|home| PathBuf::from(home).join(".config").join("secret-squirrel"))
+fn main() {
+  println!("cargo:rerun-if-changed=build.rs");
+  println!("cargo:rerun-if-changed=src/paths.rs");
+  println!("cargo:rerun-if-changed=config/ssq.yml");
+  for var in [paths::CONFIG_DIR_VAR, "XDG_CONFIG_HOME", "HOME", "APPDATA"] {
+    println!("cargo:rerun-if-env-changed={var}");
   }
-}
 
-fn main() {
   let manifest_dir =
     env::var("CARGO_MANIFEST_DIR").expect("Failed to get manifest dir");
   let config_src = PathBuf::from(&manifest_dir).join("config").join("ssq.yml");
@@ -31,12 +23,11 @@ fn main() {
     config_src.display()
   );
 
-  if let Some(config_dir) = get_config_dir() {
-    fs::create_dir_all(&config_dir).expect("Failed to create config directory");
-    let config_dest = config_dir.join("config.yml");
-
-    fs::copy(&config_src, &config_dest).expect("Failed to copy config file");
-  } else {
-    panic!("Could not determine config directory");
+  // The base config is a convenience: `ssq` runs without one, so a sandbox
+  // that can't write it shouldn't fail the build
+  if let Err(warning) =
+    paths::install_base_config(&config_src, paths::get_config_dir())
+  {
+    println!("cargo:warning={warning}");
   }
 }
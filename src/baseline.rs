@@ -0,0 +1,101 @@
+use crate::scan::{hash_parts, Match};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// Version 2 fingerprints normalize the file path
+const BASELINE_VERSION: u32 = 2;
+
+/// Known findings that shouldn't be reported again
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Baseline {
+  version: u32,
+  findings: Vec<Finding>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Finding {
+  fingerprint: String,
+  pattern_name: String,
+  file_path: String,
+}
+
+/// The fingerprint version 1 baselines recorded, before [`Match::fingerprint`]
+/// normalized the file path
+pub(crate) fn legacy_fingerprint(m: &Match) -> String {
+  hash_parts(&[&m.pattern_name, &m.file_path, &m.secret])
+}
+
+impl Baseline {
+  pub fn from_matches(matches: &[Match]) -> Self {
+    let mut baseline = Self {
+      version: BASELINE_VERSION,
+      findings: Vec::new(),
+    };
+    baseline.extend(matches);
+    baseline
+  }
+
+  /// Record `matches` as known too, keeping the findings sorted
+  pub fn extend(&mut self, matches: &[Match]) {
+    let mut seen: HashSet<String> = self
+      .findings
+      .iter()
+      .map(|f| f.fingerprint.clone())
+      .collect();
+    self.findings.extend(
+      matches
+        .iter()
+        .map(|m| Finding {
+          fingerprint: m.fingerprint(),
+          pattern_name: m.pattern_name.clone(),
+          file_path: m.file_path.clone(),
+        })
+        .filter(|f| seen.insert(f.fingerprint.clone())),
+    );
+    self.findings.sort_by(|a, b| {
+      (&a.file_path, &a.pattern_name, &a.fingerprint).cmp(&(
+        &b.file_path,
+        &b.pattern_name,
+        &b.fingerprint,
+      ))
+    });
+  }
+
+  pub fn load(path: &Path) -> Result<Self> {
+    let contents = fs::read_to_string(path).with_context(|| {
+      format!("Failed to read baseline: {}", path.display())
+    })?;
+    serde_json::from_str(&contents)
+      .with_context(|| format!("Failed to parse baseline: {}", path.display()))
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+      .with_context(|| format!("Failed to write baseline: {}", path.display()))
+  }
+
+  pub fn len(&self) -> usize {
+    self.findings.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.findings.is_empty()
+  }
+
+  /// Whether this baseline was written before fingerprints normalized the
+  /// file path, so matches should also be checked against the old form
+  pub fn has_legacy_fingerprints(&self) -> bool {
+    self.version < BASELINE_VERSION
+  }
+
+  pub fn fingerprints(&self) -> HashSet<&str> {
+    self
+      .findings
+      .iter()
+      .map(|f| f.fingerprint.as_str())
+      .collect()
+  }
+}
@@ -1,12 +1,17 @@
 use crate::{debug::debug, paths};
 use anyhow::Result;
 use console::style;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
 use serde::{Deserialize, Serialize};
 // Add serde_with for custom serialization
 use serde_with::{serde_as, DisplayFromStr};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -15,25 +20,488 @@ pub enum ConfigError {
   IoError(#[from] std::io::Error),
   #[error("Failed to parse config file: {0}")]
   ParseError(#[from] serde_yaml::Error),
+  #[error("Failed to parse TOML config file: {0}")]
+  TomlError(#[from] toml::de::Error),
+  #[error("Failed to parse JSON config file: {0}")]
+  JsonError(#[from] serde_json::Error),
   #[error("No base config found")]
   NoBaseConfig,
+  #[error("Invalid regex in pattern '{name}': {error}")]
+  InvalidPattern { name: String, error: String },
+  #[error("Invalid value for '{name}': {error}")]
+  InvalidValue { name: String, error: String },
+  #[error("Circular extends: {0}")]
+  CircularExtends(String),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
 pub struct Pattern {
   pub description: Option<String>,
   pub regex: String,
-  pub severity: String,
+  pub severity: SeverityLevel,
+  /// Discard matches whose secret has at most this much Shannon entropy
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub min_entropy: Option<f64>,
+  /// Discard matches whose secret is shorter than this many characters
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub min_match_length: Option<usize>,
+  /// Only report matches with a line matching this rule nearby, such as a
+  /// `password:` key above a value
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub near: Option<ProximityRule>,
+  /// Literal strings, such as `ghp_`, at least one of which every match
+  /// contains. Content with none of them, regardless of case, is passed
+  /// over without running the regex.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub keywords: Vec<String>,
+  /// Group name used to look up a minimum severity in `category_severity`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub category: Option<String>,
+  /// Match against whole file contents, with `.` matching newlines, rather
+  /// than line by line
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub multiline: bool,
+  /// Match regardless of case, as a leading `(?i)` would
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub ignore_case: bool,
+  /// Only match where the regex starts and ends at a word boundary, as
+  /// wrapping it in `\b` would
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub word_boundary: bool,
+  /// Free-form labels such as "cloud" or "pii", for selecting patterns in
+  /// bulk and routing findings
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub tags: Vec<String>,
+  /// How to check whether a match is a live credential, with `--verify`
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub verify: Option<VerifyKind>,
+  /// Only run this pattern on files matching one of these globs
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub path_include: Vec<String>,
+  /// Never run this pattern on files matching one of these globs
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub path_exclude: Vec<String>,
+}
+
+impl Pattern {
+  /// The regex as written, wrapped in `\b` for `word_boundary`
+  pub fn full_regex(&self) -> Cow<'_, str> {
+    if self.word_boundary {
+      Cow::Owned(format!(r"\b(?:{})\b", self.regex))
+    } else {
+      Cow::Borrowed(&self.regex)
+    }
+  }
+
+  /// The exact regex the scanner compiles: `full_regex` with `ignore_case`
+  /// and `multiline` turned into a flag group around it. Inline flags in
+  /// the regex itself still apply within the group.
+  pub fn compiled_regex(&self) -> Cow<'_, str> {
+    let mut flags = String::new();
+    if self.ignore_case {
+      flags.push('i');
+    }
+    if self.multiline {
+      // Anchor `^` and `$` at each line and let `.` cross them, so a
+      // multiline match can span a whole block
+      flags.push_str("ms");
+    }
+    if flags.is_empty() {
+      self.full_regex()
+    } else {
+      Cow::Owned(format!("(?{flags}:{})", self.full_regex()))
+    }
+  }
+
+  /// Compile the regex exactly as the scanner does, with this pattern's
+  /// modifiers, reporting failures against `name`
+  pub fn matcher(&self, name: &str) -> Result<RegexMatcher, ConfigError> {
+    regex_matcher(&self.compiled_regex()).map_err(|e| {
+      ConfigError::InvalidPattern {
+        name: name.to_string(),
+        error: e.to_string(),
+      }
+    })
+  }
+
+  /// Compile the `near` rule's regex, if there is one, reporting failures
+  /// against `name`
+  pub fn near_matcher(
+    &self,
+    name: &str,
+  ) -> Result<Option<RegexMatcher>, ConfigError> {
+    self
+      .near
+      .as_ref()
+      .map(|near| {
+        regex_matcher(&near.regex).map_err(|e| ConfigError::InvalidPattern {
+          name: format!("{name}.near"),
+          error: e.to_string(),
+        })
+      })
+      .transpose()
+  }
+
+  /// The files this pattern runs on, or `None` when it runs on all of them.
+  /// `name` is used to report bad globs.
+  pub fn path_scope(
+    &self,
+    name: &str,
+  ) -> Result<Option<PathScope>, ConfigError> {
+    if self.path_include.is_empty() && self.path_exclude.is_empty() {
+      return Ok(None);
+    }
+    let build = |field: &str, globs: &[String]| {
+      let mut builder = GlobSetBuilder::new();
+      for glob in globs {
+        builder.add(Glob::new(glob).map_err(|e| {
+          ConfigError::InvalidValue {
+            name: format!("{name}.{field}"),
+            error: e.to_string(),
+          }
+        })?);
+      }
+      builder.build().map_err(|e| ConfigError::InvalidValue {
+        name: format!("{name}.{field}"),
+        error: e.to_string(),
+      })
+    };
+    Ok(Some(PathScope {
+      include: build("path_include", &self.path_include)?,
+      exclude: build("path_exclude", &self.path_exclude)?,
+    }))
+  }
+}
+
+/// A pattern's `path_include` and `path_exclude` globs
+#[derive(Debug)]
+pub struct PathScope {
+  include: GlobSet,
+  exclude: GlobSet,
+}
+
+impl PathScope {
+  /// Whether `path` is in scope. Globs match either the file name or the
+  /// whole path, where `*` also crosses directories.
+  pub fn contains(&self, path: &str) -> bool {
+    let path = Path::new(path.strip_prefix("./").unwrap_or(path));
+    let is_match = |set: &GlobSet| {
+      set.is_match(path) || path.file_name().is_some_and(|n| set.is_match(n))
+    };
+    (self.include.is_empty() || is_match(&self.include))
+      && !is_match(&self.exclude)
+  }
+}
+
+/// A companion regex that must match close to a pattern's match, for
+/// secrets that only stand out next to a keyword on another line
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct ProximityRule {
+  pub regex: String,
+  /// How many lines before or after the match the companion may be on. At
+  /// 0 it must be on the same line.
+  #[serde(default = "default_proximity_lines")]
+  pub lines: usize,
+}
+
+fn default_proximity_lines() -> usize {
+  1
+}
+
+/// Services whose credentials `--verify` can check
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyKind {
+  /// An access key ID or secret access key, paired with the other half
+  /// found in the same file
+  Aws,
+  /// A personal access, OAuth or app token
+  Github,
+}
+
+/// What a config's `ignore_paths` are matched relative to
+#[derive(
+  Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnorePathsBase {
+  /// The path being scanned
+  #[default]
+  Root,
+  /// The directory holding the config file, as for a `.gitignore`
+  Config,
+}
+
+impl IgnorePathsBase {
+  fn is_root(&self) -> bool {
+    *self == Self::Root
+  }
+}
+
+/// Which config wins where the base and local configs both set something,
+/// from `config_precedence` or `--config-precedence`
+#[derive(
+  Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigPrecedence {
+  /// The local config's patterns and settings win, so a project can tune
+  /// the base config
+  #[default]
+  Local,
+  /// The base config's patterns and settings win, so a local config can add
+  /// to them but not weaken them
+  Base,
+}
+
+impl FromStr for ConfigPrecedence {
+  type Err = String;
+
+  fn from_str(precedence: &str) -> Result<Self, Self::Err> {
+    match precedence.to_ascii_lowercase().as_str() {
+      "local" => Ok(Self::Local),
+      "base" => Ok(Self::Base),
+      _ => Err(format!(
+        "unknown config precedence '{precedence}', expected local or base"
+      )),
+    }
+  }
+}
+
+const SCHEMA_URL: &str = "https://raw.githubusercontent.com/therealklanni/secret-squirrel/main/schema/ssq.schema.json";
+
+/// Base config file names, in order of preference
+const BASE_CONFIG_FILES: &[&str] =
+  &["config.yml", "config.yaml", "config.toml", "config.json"];
+
+/// Per-project config file, looked up from the current directory upwards
+pub const LOCAL_CONFIG_FILE: &str = ".ssq.yml";
+
+/// Comments written above each setting of a scaffolded config, in order
+const SCAFFOLD_SECTIONS: &[(&str, &str)] = &[
+  ("severity", "Only report matches at or above this severity"),
+  (
+    "ignore_pattern_behavior",
+    "Whether ignore_patterns below extend (merge) or replace the base config's",
+  ),
+  (
+    "ignore_paths_behavior",
+    "Whether ignore_paths below extend (merge) or replace the base config's",
+  ),
+  (
+    "ignore_patterns",
+    "Lines matching any of these regexes are never reported. Prefix one with\n\
+     'value:' to match it against the secret alone.",
+  ),
+  (
+    "ignore_paths",
+    "Glob patterns for files and directories to skip",
+  ),
+  (
+    "patterns",
+    "Detection rules, added to or overriding the base config's by name. The\n\
+     first capture group, if any, is reported as the secret.",
+  ),
+];
+
+/// Fraction of non-text bytes above which a file is treated as binary
+const DEFAULT_BINARY_THRESHOLD: f64 = 0.3;
+
+/// Marks an `ignore_patterns` entry that is matched against the secret
+/// itself rather than the whole line, e.g. `value:\.example$`
+pub const VALUE_IGNORE_PREFIX: &str = "value:";
+
+/// The regex of an `ignore_patterns` entry, and whether it's matched
+/// against the secret rather than the line
+pub fn ignore_pattern_regex(entry: &str) -> (&str, bool) {
+  match entry.strip_prefix(VALUE_IGNORE_PREFIX) {
+    Some(regex) => (regex, true),
+    None => (entry, false),
+  }
+}
+
+/// Name under which entropy detector matches are reported
+pub const ENTROPY_PATTERN_NAME: &str = "high-entropy";
+/// Name under which `secret_keywords` matches are reported
+pub const KEYWORD_PATTERN_NAME: &str = "secret-keyword";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct EntropyConfig {
+  /// Minimum Shannon entropy (bits per character) for a base64-like token
+  #[serde(default = "default_entropy_threshold")]
+  pub threshold: f64,
+  /// Tokens shorter than this are never flagged
+  #[serde(default = "default_entropy_min_length")]
+  pub min_length: usize,
 }
 
-#[derive(Debug, PartialEq, Ord, PartialOrd, Eq)]
+impl Default for EntropyConfig {
+  fn default() -> Self {
+    Self {
+      threshold: default_entropy_threshold(),
+      min_length: default_entropy_min_length(),
+    }
+  }
+}
+
+fn default_entropy_threshold() -> f64 {
+  4.5
+}
+
+fn default_entropy_min_length() -> usize {
+  20
+}
+
+/// Compile a regex as the scanner searches with it: `^` and `$` match at
+/// line boundaries, and `$` matches before a `\r\n` line ending as well as a
+/// `\n`, so files with Windows line endings match as Unix ones do
+pub fn regex_matcher(regex: &str) -> Result<RegexMatcher, grep_regex::Error> {
+  RegexMatcherBuilder::new()
+    .multi_line(true)
+    .crlf(true)
+    // `crlf` also bans line terminators from matches, which `multiline`
+    // patterns need to cross
+    .line_terminator(None)
+    .build(regex)
+}
+
+/// Compile a regex with no modifiers, as the scanner does for built-in
+/// detectors, reporting failures against the pattern's `name`
+pub fn compile_regex(
+  name: &str,
+  regex: &str,
+) -> Result<RegexMatcher, ConfigError> {
+  regex_matcher(regex).map_err(|e| ConfigError::InvalidPattern {
+    name: name.to_string(),
+    error: e.to_string(),
+  })
+}
+
+/// Problems found by [`Config::check`]
+#[derive(Debug, Default)]
+pub struct ConfigCheck {
+  pub errors: Vec<String>,
+  pub warnings: Vec<String>,
+  /// Overly broad patterns, which `--strict` treats as errors
+  pub lints: Vec<String>,
+}
+
+/// A warning if `regex` matches an empty string or starts with an
+/// unanchored `.*`
+fn lint_pattern(
+  name: &str,
+  regex: &str,
+  matcher: &RegexMatcher,
+) -> Option<String> {
+  if matcher.is_match(b"").unwrap_or(false) {
+    Some(format!("Pattern '{name}' matches an empty string"))
+  } else if starts_with_dot_star(regex) {
+    Some(format!(
+      "Pattern '{name}' starts with an unanchored '.*' and may match whole \
+       lines"
+    ))
+  } else {
+    None
+  }
+}
+
+/// Expand each `${VAR}` in `value`. Unset variables are left as written.
+fn expand_vars(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+  let mut expanded = String::with_capacity(value.len());
+  let mut rest = value;
+  while let Some(start) = rest.find("${") {
+    expanded.push_str(&rest[..start]);
+    let after = &rest[start + 2..];
+    let Some(end) = after.find('}') else {
+      break;
+    };
+    let name = &after[..end];
+    let valid_name = !name.is_empty()
+      && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match lookup(name).filter(|_| valid_name) {
+      Some(var) => expanded.push_str(&var),
+      None => {
+        if valid_name {
+          debug(&format!("${{{name}}} is not set, leaving it unexpanded"));
+        }
+        expanded.push_str(&rest[start..start + end + 3]);
+      }
+    }
+    rest = &after[end + 1..];
+  }
+  expanded.push_str(rest);
+  expanded
+}
+
+/// Whether `regex` begins with `.*`, ignoring leading flag groups like `(?i)`
+fn starts_with_dot_star(regex: &str) -> bool {
+  let mut rest = regex;
+  while let Some((flags, tail)) =
+    rest.strip_prefix("(?").and_then(|r| r.split_once(')'))
+  {
+    if flags.is_empty()
+      || !flags.chars().all(|c| c.is_ascii_alphabetic() || c == '-')
+    {
+      break;
+    }
+    rest = tail;
+  }
+  rest.starts_with(".*")
+}
+
+fn is_known_severity(severity: &str) -> bool {
+  ["low", "medium", "high", "critical"]
+    .iter()
+    .any(|level| severity.eq_ignore_ascii_case(level))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Ord, PartialOrd, Eq, Default)]
 pub enum SeverityLevel {
+  #[default]
   Low,
   Medium,
   High,
   Critical,
 }
 
+impl SeverityLevel {
+  /// The level as written in configs and output
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SeverityLevel::Critical => "CRITICAL",
+      SeverityLevel::High => "HIGH",
+      SeverityLevel::Medium => "MEDIUM",
+      SeverityLevel::Low => "LOW",
+    }
+  }
+}
+
+impl Serialize for SeverityLevel {
+  fn serialize<S: serde::Serializer>(
+    &self,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.as_str())
+  }
+}
+
+/// Any case is accepted, but unlike `From<&str>`, an unknown level is an
+/// error rather than `Low`, so a typo can't quietly demote a pattern
+impl<'de> Deserialize<'de> for SeverityLevel {
+  fn deserialize<D: serde::Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let level = String::deserialize(deserializer)?;
+    if !is_known_severity(&level) {
+      return Err(serde::de::Error::unknown_variant(
+        &level,
+        &["low", "medium", "high", "critical"],
+      ));
+    }
+    Ok(Self::from(level.as_str()))
+  }
+}
+
 impl From<&str> for SeverityLevel {
   fn from(s: &str) -> Self {
     match s.to_lowercase().as_str() {
@@ -48,6 +516,18 @@ impl From<&str> for SeverityLevel {
 #[derive(Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct Config {
+  /// Other config files, relative to this one, merged in beneath it in
+  /// order
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub extends: Vec<String>,
+  /// Set to false in a local config to skip the built-in base config, so
+  /// only the local config's patterns are used
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub use_base_config: Option<bool>,
+  /// Whether the base or local config wins where both set something. Read
+  /// from the base config first, so it can't be undone locally.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub config_precedence: Option<ConfigPrecedence>,
   #[serde(default)]
   pub patterns: HashMap<String, Pattern>,
   #[serde(default)]
@@ -55,14 +535,63 @@ pub struct Config {
   #[serde(default)]
   pub ignore_paths: Option<Vec<String>>,
   #[serde(default)]
-  pub severity: Option<String>,
+  pub severity: Option<SeverityLevel>,
+  /// Minimum severity per pattern category, used instead of `severity` for
+  /// patterns in that category
+  #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+  pub category_severity: HashMap<String, SeverityLevel>,
   #[serde(default = "default_ignore_behavior")]
   pub ignore_pattern_behavior: String,
   #[serde(default = "default_ignore_behavior")]
   pub ignore_paths_behavior: String,
+  #[serde(default, skip_serializing_if = "IgnorePathsBase::is_root")]
+  pub ignore_paths_relative_to: IgnorePathsBase,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub entropy: Option<EntropyConfig>,
+  /// Words that mark a key as secret, such as "TOKEN". Any non-empty value
+  /// assigned to a key containing one of them in a `KEY=VALUE` or
+  /// `KEY: VALUE` line is reported.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub secret_keywords: Vec<String>,
+  /// Literal values, such as documented example keys, that are never
+  /// reported even when a pattern matches them
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub allowed_values: Vec<String>,
+  /// Files larger than this many bytes are skipped
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub max_file_size: Option<u64>,
+  /// When non-empty, only files with one of these extensions, such as
+  /// "env" or ".yaml", are scanned
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub scan_extensions: Option<Vec<String>>,
+  /// Files whose sampled bytes are more than this fraction non-text are
+  /// skipped as binary
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub binary_threshold: Option<f64>,
   #[serde(skip)]
   severity_filter: Option<SeverityLevel>,
   #[serde(skip)]
+  rule_filter: Vec<String>,
+  #[serde(skip)]
+  rule_exclusions: Vec<String>,
+  /// Base config patterns replaced by a local one with the same name
+  #[serde(skip)]
+  overridden_patterns: Vec<String>,
+  /// Local config settings dropped because the base config takes
+  /// precedence
+  #[serde(skip)]
+  ignored_local_settings: Vec<&'static str>,
+  #[serde(skip)]
+  lints: Vec<String>,
+  /// Directories that ignore paths from configs with
+  /// `ignore_paths_relative_to: config` are anchored to
+  #[serde(skip)]
+  ignore_path_origins: HashMap<String, PathBuf>,
+  #[serde(skip)]
+  tag_filter: Vec<String>,
+  #[serde(skip)]
+  tag_exclusions: Vec<String>,
+  #[serde(skip)]
   pub computed_severity: Option<SeverityLevel>,
 }
 
@@ -72,6 +601,50 @@ fn default_ignore_behavior() -> String {
 
 impl Config {
   fn merge_config(&mut self, other: &Self) {
+    // Local patterns override base patterns with the same name
+    for (name, pattern) in &other.patterns {
+      if self
+        .patterns
+        .insert(name.clone(), pattern.clone())
+        .is_some()
+      {
+        self.overridden_patterns.push(name.clone());
+      }
+    }
+
+    for (category, severity) in &other.category_severity {
+      self.category_severity.insert(category.clone(), *severity);
+    }
+
+    if other.entropy.is_some() {
+      self.entropy.clone_from(&other.entropy);
+    }
+    for keyword in &other.secret_keywords {
+      if !self.secret_keywords.contains(keyword) {
+        self.secret_keywords.push(keyword.clone());
+      }
+    }
+    for value in &other.allowed_values {
+      if !self.allowed_values.contains(value) {
+        self.allowed_values.push(value.clone());
+      }
+    }
+    if other.max_file_size.is_some() {
+      self.max_file_size = other.max_file_size;
+    }
+    if other.scan_extensions.is_some() {
+      self.scan_extensions.clone_from(&other.scan_extensions);
+    }
+    if other.binary_threshold.is_some() {
+      self.binary_threshold = other.binary_threshold;
+    }
+    if other.use_base_config.is_some() {
+      self.use_base_config = other.use_base_config;
+    }
+    if other.config_precedence.is_some() {
+      self.config_precedence = other.config_precedence;
+    }
+
     // Apply local config's behavior settings first
     if other.ignore_pattern_behavior == "replace" {
       self.ignore_pattern_behavior = other.ignore_pattern_behavior.to_string();
@@ -93,6 +666,9 @@ impl Config {
       };
     }
 
+    for (path, dir) in &other.ignore_path_origins {
+      self.ignore_path_origins.insert(path.clone(), dir.clone());
+    }
     if other.ignore_paths.is_some() {
       self.ignore_paths = if self.ignore_paths_behavior == "replace" {
         debug("Replacing ignore paths with local config");
@@ -110,39 +686,262 @@ impl Config {
 
   pub fn load_with_path(
     config_path: Option<PathBuf>,
+    use_base_config: bool,
+    precedence: Option<ConfigPrecedence>,
   ) -> Result<Self, ConfigError> {
-    // Load base config
+    let mut config =
+      Self::load_merged(config_path, use_base_config, precedence)?;
+    config.lints = config.validate()?;
+    Ok(config)
+  }
+
+  /// Load and merge the base and local configs without validating them, so
+  /// every problem can be reported by [`Config::check`]. The built-in base
+  /// config is skipped unless `use_base_config` is set and, under local
+  /// precedence, the local config allows it; a `config_path` is always
+  /// loaded. `precedence` picks which of the two wins, over the base
+  /// config's `config_precedence` and then the local config's.
+  pub fn load_merged(
+    config_path: Option<PathBuf>,
+    use_base_config: bool,
+    precedence: Option<ConfigPrecedence>,
+  ) -> Result<Self, ConfigError> {
+    let local_config = Self::load_local_config()?;
+    let explicit_config = config_path.is_some();
+
+    // Load base config. It's read even if the local config opts out of it,
+    // since it may take precedence and so overrule that.
     let mut base_config = if let Some(path) = config_path {
       debug(&format!("Loading config from: {}", path.display()));
       Self::load_from_path(path)?
-    } else {
+    } else if use_base_config {
       Self::load_base_config()?
+    } else {
+      debug("Skipping base config");
+      Self::default()
     };
 
-    // Initialize base config's computed severity
-    if let Some(ref sev) = base_config.severity {
-      base_config.computed_severity = Some(SeverityLevel::from(sev.as_str()));
+    // A local config can't lower the precedence the base config asks for
+    let precedence = precedence
+      .or(base_config.config_precedence)
+      .or(local_config.as_ref().and_then(|c| c.config_precedence))
+      .unwrap_or_default();
+
+    let opted_out = local_config
+      .as_ref()
+      .and_then(|local_config| local_config.use_base_config)
+      == Some(false);
+    if opted_out && precedence == ConfigPrecedence::Local && !explicit_config {
+      debug("Skipping base config");
+      base_config = Self::default();
     }
 
-    // Try to load and merge local config
-    if let Ok(local_config) = Self::load_local_config() {
-      debug("Merging local config with base config");
-      base_config.merge_config(&local_config);
+    // Merge the two, the winning config last
+    let mut config = match (local_config, precedence) {
+      (Some(local_config), ConfigPrecedence::Local) => {
+        debug("Merging local config with base config");
+        base_config.merge_config(&local_config);
+        if local_config.severity.is_some() {
+          base_config.severity = local_config.severity;
+        }
+        base_config
+      }
+      (Some(mut local_config), ConfigPrecedence::Base) => {
+        debug("Merging base config over local config");
+        // The local config can add patterns but not suppress the base
+        // config's findings, so its ignores and allowed values are dropped,
+        // as are the settings that decide what gets scanned and reported
+        let ignored_local_settings = [
+          ("use_base_config", opted_out),
+          (
+            "ignore_patterns",
+            local_config.ignore_patterns.take().is_some(),
+          ),
+          ("ignore_paths", local_config.ignore_paths.take().is_some()),
+          (
+            "allowed_values",
+            !std::mem::take(&mut local_config.allowed_values).is_empty(),
+          ),
+          ("severity", local_config.severity.take().is_some()),
+          (
+            "category_severity",
+            !std::mem::take(&mut local_config.category_severity).is_empty(),
+          ),
+          ("entropy", local_config.entropy.take().is_some()),
+          ("max_file_size", local_config.max_file_size.take().is_some()),
+          (
+            "scan_extensions",
+            local_config.scan_extensions.take().is_some(),
+          ),
+          (
+            "binary_threshold",
+            local_config.binary_threshold.take().is_some(),
+          ),
+        ];
+        local_config.ignored_local_settings = ignored_local_settings
+          .into_iter()
+          .filter_map(|(setting, set)| set.then_some(setting))
+          .collect();
+        local_config.ignore_path_origins.clear();
+        local_config.ignore_pattern_behavior = default_ignore_behavior();
+        local_config.ignore_paths_behavior = default_ignore_behavior();
+        local_config.merge_config(&base_config);
+        local_config.severity = base_config.severity;
+        local_config
+      }
+      (None, _) => {
+        debug("Using base config");
+        base_config
+      }
+    };
+    config.config_precedence = Some(precedence);
+    config.computed_severity = config.severity;
+
+    config.expand_env_vars(|name| std::env::var(name).ok());
+    Ok(config)
+  }
 
-      // Update severity if local config has one
-      if let Some(ref sev) = local_config.severity {
-        base_config.severity = Some(sev.clone());
-        base_config.computed_severity = Some(SeverityLevel::from(sev.as_str()));
+  /// Replace `${VAR}` in ignore paths and patterns with the variable's
+  /// value, so a shared config can point at machine-specific locations
+  fn expand_env_vars(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+    for list in [&mut self.ignore_paths, &mut self.ignore_patterns] {
+      for entry in list.iter_mut().flatten() {
+        *entry = expand_vars(entry, &lookup);
       }
+    }
+    self.ignore_path_origins = std::mem::take(&mut self.ignore_path_origins)
+      .into_iter()
+      .map(|(path, dir)| (expand_vars(&path, &lookup), dir))
+      .collect();
+  }
 
-      Ok(base_config)
-    } else {
-      debug("Using base config");
-      Ok(base_config)
+  /// Compile every pattern's regex so a broken rule is reported up front
+  /// rather than silently skipped during a scan. Returns the lint warnings
+  /// for patterns that compiled but look too broad.
+  fn validate(&self) -> Result<Vec<String>, ConfigError> {
+    let mut names: Vec<_> = self.patterns.keys().collect();
+    names.sort();
+
+    let mut lints = Vec::new();
+    for name in names {
+      let pattern = &self.patterns[name];
+      let matcher = pattern.matcher(name)?;
+      lints.extend(lint_pattern(name, &pattern.regex, &matcher));
+      pattern.near_matcher(name)?;
+      pattern.path_scope(name)?;
+    }
+
+    self.validate_binary_threshold()?;
+    lints.extend(self.lint_ignore_patterns());
+    Ok(lints)
+  }
+
+  /// Warnings for `ignore_patterns` entries that aren't valid regexes. A
+  /// scan skips them rather than failing, so one typo doesn't stop every
+  /// other entry from applying.
+  fn lint_ignore_patterns(&self) -> Vec<String> {
+    self
+      .ignore_patterns
+      .iter()
+      .flatten()
+      .filter_map(|entry| {
+        let (regex, _) = ignore_pattern_regex(entry);
+        regex_matcher(regex).err().map(|e| {
+          format!("Ignore pattern '{entry}' is invalid and is skipped: {e}")
+        })
+      })
+      .collect()
+  }
+
+  /// Warnings for patterns broad enough to be a likely mistake, found while
+  /// loading. These don't stop a scan unless `--strict` is given.
+  pub fn lints(&self) -> &[String] {
+    &self.lints
+  }
+
+  fn validate_binary_threshold(&self) -> Result<(), ConfigError> {
+    match self.binary_threshold {
+      Some(threshold) if !(0.0..=1.0).contains(&threshold) => {
+        Err(ConfigError::InvalidValue {
+          name: "binary_threshold".to_string(),
+          error: format!("{threshold} is not between 0 and 1"),
+        })
+      }
+      _ => Ok(()),
+    }
+  }
+
+  /// Every problem with the config, for `ssq validate`. Unlike loading,
+  /// this doesn't stop at the first error.
+  pub fn check(&self) -> ConfigCheck {
+    let mut check = ConfigCheck::default();
+    let mut names: Vec<_> = self.patterns.keys().collect();
+    names.sort();
+
+    for name in names {
+      let pattern = &self.patterns[name];
+      match pattern.matcher(name) {
+        Ok(matcher) => {
+          check
+            .lints
+            .extend(lint_pattern(name, &pattern.regex, &matcher));
+        }
+        Err(e) => check.errors.push(e.to_string()),
+      }
+      if let Err(e) = pattern.near_matcher(name) {
+        check.errors.push(e.to_string());
+      }
+      if let Err(e) = pattern.path_scope(name) {
+        check.errors.push(e.to_string());
+      }
+    }
+
+    if let Err(e) = self.validate_binary_threshold() {
+      check.errors.push(e.to_string());
+    }
+    check.lints.extend(self.lint_ignore_patterns());
+
+    let mut overridden = self.overridden_patterns.clone();
+    overridden.sort();
+    overridden.dedup();
+    for name in overridden {
+      check.warnings.push(
+        if self.config_precedence == Some(ConfigPrecedence::Base) {
+          format!(
+            "Pattern '{name}' in {LOCAL_CONFIG_FILE} is replaced by the base \
+             config's"
+          )
+        } else {
+          format!(
+            "Pattern '{name}' in {LOCAL_CONFIG_FILE} replaces the base config's"
+          )
+        },
+      );
+    }
+
+    for setting in &self.ignored_local_settings {
+      check.warnings.push(format!(
+        "'{setting}' in {LOCAL_CONFIG_FILE} is ignored because the base config \
+         takes precedence"
+      ));
     }
+
+    check
   }
 
-  fn load_from_path(path: PathBuf) -> Result<Self, ConfigError> {
+  /// Load a single config file and the files it extends, without the base
+  /// or local configs
+  pub(crate) fn load_from_path(path: PathBuf) -> Result<Self, ConfigError> {
+    Self::load_extending(&path, &mut Vec::new())
+  }
+
+  /// Parse a config file with the files it `extends` merged in beneath it,
+  /// so its own settings win. `chain` holds the files being loaded, to
+  /// catch a file that ends up extending itself.
+  fn load_extending(
+    path: &Path,
+    chain: &mut Vec<PathBuf>,
+  ) -> Result<Self, ConfigError> {
     if !path.exists() {
       return Err(ConfigError::IoError(std::io::Error::new(
         std::io::ErrorKind::NotFound,
@@ -150,45 +949,220 @@ impl Config {
       )));
     }
 
-    Ok(serde_yaml::from_str(&fs::read_to_string(path)?)?)
+    let canonical = path.canonicalize()?;
+    if chain.contains(&canonical) {
+      let cycle: Vec<_> = chain
+        .iter()
+        .chain([&canonical])
+        .map(|path| path.display().to_string())
+        .collect();
+      return Err(ConfigError::CircularExtends(cycle.join(" -> ")));
+    }
+
+    let mut config = Self::parse_file(path)?;
+    if config.ignore_paths_relative_to == IgnorePathsBase::Config {
+      let dir = canonical.parent().unwrap_or(&canonical);
+      for ignore_path in config.ignore_paths.iter().flatten() {
+        config
+          .ignore_path_origins
+          .insert(ignore_path.clone(), dir.to_path_buf());
+      }
+    }
+    if config.extends.is_empty() {
+      return Ok(config);
+    }
+
+    chain.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = Self::default();
+    for extended in &config.extends {
+      let extended_path = dir.join(extended);
+      debug(&format!("Extending config: {}", extended_path.display()));
+      merged.merge_extended(&Self::load_extending(&extended_path, chain)?);
+    }
+    chain.pop();
+
+    merged.merge_extended(&config);
+    merged.extends = config.extends;
+    Ok(merged)
+  }
+
+  /// Merge `other` over this config, including its severity
+  fn merge_extended(&mut self, other: &Self) {
+    self.merge_config(other);
+    if other.severity.is_some() {
+      self.severity = other.severity;
+    }
+  }
+
+  /// Parse a config file in the format given by its extension, defaulting
+  /// to YAML
+  fn parse_file(path: &Path) -> Result<Self, ConfigError> {
+    let contents = fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+      Some("toml") => Ok(toml::from_str(&contents)?),
+      Some("json") => Ok(serde_json::from_str(&contents)?),
+      _ => Ok(serde_yaml::from_str(&contents)?),
+    }
   }
 
   fn load_base_config() -> Result<Self, ConfigError> {
     let config_dir =
       paths::get_config_dir().ok_or(ConfigError::NoBaseConfig)?;
-    let base_config_path = config_dir.join("config.yml");
+    let Some(base_config_path) = BASE_CONFIG_FILES
+      .iter()
+      .map(|name| config_dir.join(name))
+      .find(|path| path.exists())
+    else {
+      debug("No base config found");
+      return Ok(Self::default());
+    };
     debug(&format!(
       "Loading base config from: {}",
       base_config_path.display()
     ));
 
-    if !base_config_path.exists() {
-      debug("No base config found");
-      return Ok(Self::default());
-    }
+    Self::load_from_path(base_config_path)
+  }
 
-    Ok(serde_yaml::from_str(&fs::read_to_string(
-      &base_config_path,
-    )?)?)
+  /// The local config for the current directory, or `None` if there's
+  /// none. One that exists but can't be read or parsed is an error, so a
+  /// typo doesn't quietly drop its patterns.
+  fn load_local_config() -> Result<Option<Self>, ConfigError> {
+    let Some(local_path) = Self::find_local_config(&std::env::current_dir()?)
+    else {
+      return Ok(None);
+    };
+
+    debug(&format!("Found local config at: {}", local_path.display()));
+    Self::load_from_path(local_path).map(Some)
   }
 
-  fn load_local_config() -> Result<Self, ConfigError> {
-    let local_path = PathBuf::from(".ssq.yml");
+  /// The local config in effect for the current directory, or where one
+  /// would be written if there's none
+  pub fn local_config_path() -> Result<PathBuf, ConfigError> {
+    let dir = std::env::current_dir()?;
+    Ok(
+      Self::find_local_config(&dir)
+        .unwrap_or_else(|| dir.join(LOCAL_CONFIG_FILE)),
+    )
+  }
 
-    if !local_path.exists() {
-      return Ok(Self::default());
+  /// The nearest local config in `start` or its parents, stopping at the
+  /// root of the enclosing git repository so a parent repository's or the
+  /// home directory's config isn't picked up
+  fn find_local_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+      let candidate = dir.join(LOCAL_CONFIG_FILE);
+      if candidate.is_file() {
+        return Some(candidate);
+      }
+      if dir.join(".git").exists() {
+        break;
+      }
     }
-
-    debug(&format!("Found local config at: {}", local_path.display()));
-    Ok(serde_yaml::from_str(&fs::read_to_string(&local_path)?)?)
+    None
   }
 
   pub fn set_severity_filter(&mut self, level: &str) {
     // CLI flag updates both the filter and the base severity
-    let level = level.to_string().to_uppercase();
-    self.severity_filter = Some(SeverityLevel::from(level.as_str()));
-    self.severity = Some(level.clone());
-    self.computed_severity = Some(SeverityLevel::from(level.as_str()));
+    let level = SeverityLevel::from(level);
+    self.severity_filter = Some(level);
+    self.severity = Some(level);
+    self.computed_severity = Some(level);
+  }
+
+  /// Restrict scanning to the `include`d rules (all rules when empty),
+  /// minus any `exclude`d ones. Returns the names that match no rule.
+  pub fn set_rule_filter(
+    &mut self,
+    include: Vec<String>,
+    exclude: Vec<String>,
+  ) -> Vec<String> {
+    let unknown = include
+      .iter()
+      .chain(&exclude)
+      .filter(|name| !self.has_rule(name))
+      .cloned()
+      .collect();
+
+    self.rule_filter = include;
+    self.rule_exclusions = exclude;
+    unknown
+  }
+
+  /// Restrict scanning to patterns with one of the `include`d tags (all
+  /// patterns when empty), minus those with any `exclude`d tag. Returns the
+  /// tags no pattern has.
+  pub fn set_tag_filter(
+    &mut self,
+    include: Vec<String>,
+    exclude: Vec<String>,
+  ) -> Vec<String> {
+    let unknown = include
+      .iter()
+      .chain(&exclude)
+      .filter(|tag| !self.patterns.values().any(|p| p.tags.contains(tag)))
+      .cloned()
+      .collect();
+
+    self.tag_filter = include;
+    self.tag_exclusions = exclude;
+    unknown
+  }
+
+  fn has_rule(&self, name: &str) -> bool {
+    self.patterns.contains_key(name)
+      || (name == ENTROPY_PATTERN_NAME && self.entropy.is_some())
+      || (name == KEYWORD_PATTERN_NAME && !self.secret_keywords.is_empty())
+  }
+
+  pub fn is_rule_enabled(&self, name: &str) -> bool {
+    // The entropy and keyword detectors aren't patterns, so have no tags
+    let tags = self.patterns.get(name).map_or(&[][..], |p| &p.tags[..]);
+    (self.rule_filter.is_empty() || self.rule_filter.iter().any(|r| r == name))
+      && !self.rule_exclusions.iter().any(|r| r == name)
+      && (self.tag_filter.is_empty()
+        || self.tag_filter.iter().any(|t| tags.contains(t)))
+      && !self.tag_exclusions.iter().any(|t| tags.contains(t))
+  }
+
+  pub fn is_allowed_value(&self, value: &str) -> bool {
+    self.allowed_values.iter().any(|allowed| allowed == value)
+  }
+
+  /// Whether `scan_extensions` lets `path` be scanned. Extensions match the
+  /// end of the file name regardless of case, so `env` covers both `.env`
+  /// and `prod.env`, and `tar.gz` works as written.
+  pub fn has_scanned_extension(&self, path: &Path) -> bool {
+    let extensions = match self.scan_extensions {
+      Some(ref extensions) if !extensions.is_empty() => extensions,
+      _ => return true,
+    };
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      return false;
+    };
+    let name = name.to_ascii_lowercase();
+    extensions.iter().any(|extension| {
+      let extension = extension.trim_start_matches('.').to_ascii_lowercase();
+      name
+        .strip_suffix(extension.as_str())
+        .is_some_and(|stem| stem.ends_with('.'))
+    })
+  }
+
+  /// The directory `ignore_path` is anchored to, when it came from a config
+  /// with `ignore_paths_relative_to: config`. Otherwise it's matched from
+  /// the scan root.
+  pub fn ignore_path_origin(&self, ignore_path: &str) -> Option<&Path> {
+    self
+      .ignore_path_origins
+      .get(ignore_path)
+      .map(PathBuf::as_path)
+  }
+
+  pub fn binary_threshold(&self) -> f64 {
+    self.binary_threshold.unwrap_or(DEFAULT_BINARY_THRESHOLD)
   }
 
   pub fn get_effective_severity(&self) -> Option<&SeverityLevel> {
@@ -199,48 +1173,143 @@ impl Config {
       .or(self.computed_severity.as_ref())
   }
 
+  /// Whether `pattern` is at or above its category's minimum severity, or
+  /// the global one if its category has none. A `--severity` filter always
+  /// applies on top.
   pub fn meets_severity(&self, pattern: &Pattern) -> bool {
-    if let Some(min_severity) = self.get_effective_severity() {
-      let pattern_severity = SeverityLevel::from(pattern.severity.as_str());
+    let pattern_severity = pattern.severity;
+    let category_floor = pattern
+      .category
+      .as_ref()
+      .and_then(|category| self.category_severity.get(category))
+      .copied();
+
+    if let Some(floor) = category_floor {
+      pattern_severity >= floor
+        && self
+          .severity_filter
+          .as_ref()
+          .is_none_or(|filter| pattern_severity >= *filter)
+    } else if let Some(min_severity) = self.get_effective_severity() {
       pattern_severity >= *min_severity
     } else {
       true
     }
   }
 
-  pub fn get_effective_config(&self) -> ConfigDisplay {
+  /// The merged config as `--print-config` shows it, limited to the rules
+  /// that will run. With `show_compiled`, each pattern also lists the regex
+  /// the scanner compiles for it.
+  pub fn get_effective_config(&self, show_compiled: bool) -> ConfigDisplay {
     ConfigDisplay {
       severity: self
         .get_effective_severity()
-        .map_or("LOW".to_string(), |s| {
-          match s {
-            SeverityLevel::Critical => "CRITICAL",
-            SeverityLevel::High => "HIGH",
-            SeverityLevel::Medium => "MEDIUM",
-            SeverityLevel::Low => "LOW",
-          }
-          .to_string()
-        }),
+        .map_or(SeverityLevel::Low, |s| *s)
+        .as_str()
+        .to_string(),
+      config_precedence: self.config_precedence.unwrap_or_default(),
       ignore_pattern_behavior: self.ignore_pattern_behavior.clone(),
       ignore_paths_behavior: self.ignore_paths_behavior.clone(),
       ignore_patterns: self.ignore_patterns.clone().unwrap_or_default(),
       ignore_paths: self.ignore_paths.clone().unwrap_or_default(),
+      category_severity: self.category_severity.clone(),
+      allowed_values: self.allowed_values.clone(),
+      entropy: self.entropy.clone(),
+      secret_keywords: self.secret_keywords.clone(),
+      max_file_size: self.max_file_size,
+      scan_extensions: self.scan_extensions.clone(),
+      binary_threshold: self.binary_threshold(),
       patterns: self
         .patterns
         .iter()
-        .filter(|(_, p)| self.meets_severity(p))
-        .map(|(k, v)| (k.clone(), v.clone()))
+        .filter(|(name, p)| {
+          self.is_rule_enabled(name) && self.meets_severity(p)
+        })
+        .map(|(name, pattern)| {
+          let display = PatternDisplay {
+            compiled_regex: show_compiled
+              .then(|| pattern.compiled_regex().into_owned()),
+            pattern: pattern.clone(),
+          };
+          (name.clone(), display)
+        })
         .collect(),
     }
   }
 
-  pub fn print(&self) {
+  /// A commented starter config for `ssq init`. It's generated from a real
+  /// `Config` so the scaffold can't drift from the schema.
+  pub fn scaffold() -> Result<String> {
+    let example = |description: &str, regex: &str, severity: &str| Pattern {
+      description: Some(description.to_string()),
+      regex: regex.to_string(),
+      severity: SeverityLevel::from(severity),
+      ..Pattern::default()
+    };
+    let config = Self {
+      patterns: HashMap::from([
+        (
+          "internal-api-token".to_string(),
+          example(
+            "Internal API token",
+            "INTERNAL_API_TOKEN\\s*=\\s*([A-Za-z0-9]{32})",
+            "HIGH",
+          ),
+        ),
+        (
+          "db-password".to_string(),
+          Pattern {
+            min_entropy: Some(3.0),
+            ..example(
+              "Database password in a connection setting",
+              "(?i)db_pass(?:word)?\\s*[:=]\\s*['\"]?([^\\s'\"]+)",
+              "MEDIUM",
+            )
+          },
+        ),
+      ]),
+      ignore_patterns: Some(vec!["EXAMPLE_.*".to_string()]),
+      ignore_paths: Some(vec!["**/fixtures/**".to_string()]),
+      severity: Some(SeverityLevel::Low),
+      ignore_pattern_behavior: default_ignore_behavior(),
+      ignore_paths_behavior: default_ignore_behavior(),
+      ..Self::default()
+    };
+
+    let fields = serde_yaml::to_value(&config)?;
+    let mut scaffold = format!(
+      "# yaml-language-server: $schema={SCHEMA_URL}\n\
+       # Settings here are merged with the base config; see `ssq --print-config`\n"
+    );
+    for (key, comment) in SCAFFOLD_SECTIONS {
+      let value = if *key == "patterns" {
+        // Sort patterns so the scaffold is stable
+        serde_yaml::to_value(
+          config.patterns.iter().collect::<BTreeMap<_, _>>(),
+        )?
+      } else if let Some(value) = fields.get(key) {
+        value.clone()
+      } else {
+        continue;
+      };
+
+      scaffold.push('\n');
+      for line in comment.lines() {
+        scaffold.push_str(&format!("# {}\n", line.trim()));
+      }
+      scaffold
+        .push_str(&serde_yaml::to_string(&BTreeMap::from([(key, value)]))?);
+    }
+    Ok(scaffold)
+  }
+
+  pub fn print(&self, show_compiled: bool) {
     println!("{}", style("Current Configuration:").bold().cyan());
     println!("{}", style("======================").cyan());
     println!();
 
     // Just serialize the effective config directly
-    let yaml = serde_yaml::to_string(&self.get_effective_config())
+    let yaml = serde_yaml::to_string(&self.get_effective_config(show_compiled))
       .expect("Failed to serialize config");
 
     // Print the YAML with styling
@@ -275,21 +1344,69 @@ impl Config {
 #[serde(rename_all = "snake_case")]
 pub struct ConfigDisplay {
   severity: String,
+  config_precedence: ConfigPrecedence,
   #[serde_as(as = "DisplayFromStr")]
   ignore_pattern_behavior: String,
   #[serde_as(as = "DisplayFromStr")]
   ignore_paths_behavior: String,
   ignore_patterns: Vec<String>,
   ignore_paths: Vec<String>,
-  patterns: HashMap<String, Pattern>,
+  #[serde(skip_serializing_if = "HashMap::is_empty")]
+  category_severity: HashMap<String, SeverityLevel>,
+  allowed_values: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  entropy: Option<EntropyConfig>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  secret_keywords: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  max_file_size: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  scan_extensions: Option<Vec<String>>,
+  binary_threshold: f64,
+  patterns: HashMap<String, PatternDisplay>,
+}
+
+#[derive(Serialize)]
+pub struct PatternDisplay {
+  #[serde(flatten)]
+  pattern: Pattern,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  compiled_regex: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::paths::TEST_ENV;
+  use parking_lot::MutexGuard;
   use std::io::Write;
   use tempfile::{NamedTempFile, TempDir};
 
+  /// Changes into a directory for the life of a test, restoring the
+  /// previous one afterwards so later tests don't start in a deleted one
+  struct CurrentDir {
+    previous: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+  }
+
+  impl CurrentDir {
+    fn enter(dir: &Path) -> std::io::Result<Self> {
+      let lock = TEST_ENV.lock();
+      let previous = std::env::current_dir()?;
+      std::env::set_current_dir(dir)?;
+      Ok(Self {
+        previous,
+        _lock: lock,
+      })
+    }
+  }
+
+  impl Drop for CurrentDir {
+    fn drop(&mut self) {
+      let _ = std::env::set_current_dir(&self.previous);
+    }
+  }
+
   #[test]
   fn test_empty_config() {
     let config = Config::default();
@@ -318,7 +1435,7 @@ ignore_paths:
 
     let config = Config::load_from_path(temp.path().to_path_buf())?;
     assert_eq!(config.patterns.len(), 1);
-    assert_eq!(config.patterns["github"].severity, "critical");
+    assert_eq!(config.patterns["github"].severity, SeverityLevel::Critical);
     assert_eq!(
       config.patterns["github"].description,
       Some("GitHub personal access token".to_string())
@@ -329,10 +1446,98 @@ ignore_paths:
     Ok(())
   }
 
+  #[test]
+  fn test_extends() -> Result<()> {
+    let temp = TempDir::new()?;
+    std::fs::create_dir(temp.path().join("shared"))?;
+    std::fs::write(
+      temp.path().join("shared/team.yml"),
+      r"
+patterns:
+  token:
+    regex: 'tok_[a-z]+'
+    severity: low
+  npm:
+    regex: 'npm_[A-Za-z0-9]{36}'
+    severity: high
+ignore_paths:
+  - 'fixtures/*'
+severity: high
+",
+    )?;
+    std::fs::write(
+      temp.path().join("ssq.yml"),
+      r"
+extends:
+  - shared/team.yml
+patterns:
+  token:
+    regex: 'tok_[a-z0-9]+'
+    severity: critical
+ignore_paths:
+  - 'vendor/*'
+",
+    )?;
+
+    let config = Config::load_from_path(temp.path().join("ssq.yml"))?;
+    assert_eq!(config.patterns.len(), 2);
+    assert_eq!(config.patterns["token"].severity, SeverityLevel::Critical);
+    assert_eq!(config.patterns["npm"].severity, SeverityLevel::High);
+    assert_eq!(
+      config.ignore_paths,
+      Some(vec!["fixtures/*".to_string(), "vendor/*".to_string()])
+    );
+    assert_eq!(config.severity, Some(SeverityLevel::High));
+
+    // A file that extends itself through another is an error
+    std::fs::write(
+      temp.path().join("shared/team.yml"),
+      "extends:\n  - ../ssq.yml\n",
+    )?;
+    assert!(matches!(
+      Config::load_from_path(temp.path().join("ssq.yml")),
+      Err(ConfigError::CircularExtends(_))
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_compiled_regex() -> Result<()> {
+    let config: Config = serde_yaml::from_str(
+      r"
+patterns:
+  plain:
+    regex: 'tok_[a-z]+'
+    severity: high
+  modified:
+    regex: 'key|secret'
+    severity: high
+    ignore_case: true
+    word_boundary: true
+  block:
+    regex: '^-----BEGIN.*?END'
+    severity: high
+    multiline: true
+",
+    )?;
+    let compiled = |name: &str| config.patterns[name].compiled_regex();
+    assert_eq!(compiled("plain"), "tok_[a-z]+");
+    assert_eq!(compiled("modified"), r"(?i:\b(?:key|secret)\b)");
+    assert_eq!(compiled("block"), "(?ms:^-----BEGIN.*?END)");
+
+    let shown = serde_yaml::to_string(&config.get_effective_config(true))?;
+    assert!(shown.contains(r"compiled_regex: (?i:\b(?:key|secret)\b)"));
+    let hidden = serde_yaml::to_string(&config.get_effective_config(false))?;
+    assert!(!hidden.contains("compiled_regex"));
+
+    Ok(())
+  }
+
   #[test]
   fn test_config_override() -> Result<(), Box<dyn std::error::Error>> {
     let temp_dir = TempDir::new()?;
-    std::env::set_current_dir(&temp_dir)?;
+    let _cwd = CurrentDir::enter(temp_dir.path())?;
 
     // Create base config
     let base_config = r"
@@ -367,18 +1572,21 @@ ignore_patterns:
   - 'DUMMY_.*'
 ignore_paths:
   - 'examples/*'
+ignore_pattern_behavior: replace
+ignore_paths_behavior: replace
 ";
     std::fs::write(".ssq.yml", local_config)?;
 
     // Load config (this should load both and merge correctly)
-    let config = Config::load_with_path(Some(PathBuf::from("config.yml")))?;
+    let config =
+      Config::load_with_path(Some(PathBuf::from("config.yml")), true, None)?;
 
     // Verify pattern merging
     assert_eq!(config.patterns.len(), 3); // github (overridden) + aws (preserved) + npm (new)
 
     // Check github pattern was overridden
     assert_eq!(config.patterns["github"].regex, "gh[pat]-[0-9a-f]{40}");
-    assert_eq!(config.patterns["github"].severity, "high");
+    assert_eq!(config.patterns["github"].severity, SeverityLevel::High);
     assert_eq!(
       config.patterns["github"].description,
       Some("GitHub PAT".to_string())
@@ -386,16 +1594,574 @@ ignore_paths:
 
     // Check aws pattern was preserved
     assert_eq!(config.patterns["aws"].regex, "AKIA.*");
-    assert_eq!(config.patterns["aws"].severity, "high");
+    assert_eq!(config.patterns["aws"].severity, SeverityLevel::High);
 
     // Check npm pattern was added
     assert_eq!(config.patterns["npm"].regex, "npm_[A-Za-z0-9]{64}");
-    assert_eq!(config.patterns["npm"].severity, "critical");
+    assert_eq!(config.patterns["npm"].severity, SeverityLevel::Critical);
 
     // Verify ignore lists were replaced
     assert_eq!(config.ignore_patterns, Some(vec!["DUMMY_.*".to_string()]));
     assert_eq!(config.ignore_paths, Some(vec!["examples/*".to_string()]));
 
+    // Without the base config, only the local patterns are left. An explicit
+    // --config is still loaded.
+    let local_names = || {
+      let mut names: Vec<_> = Config::load_merged(None, false, None)?
+        .patterns
+        .into_keys()
+        .collect();
+      names.sort();
+      Ok::<_, ConfigError>(names)
+    };
+    assert_eq!(local_names()?, ["github", "npm"]);
+    std::fs::write(
+      ".ssq.yml",
+      format!("{local_config}use_base_config: false\n"),
+    )?;
+    let config = Config::load_merged(None, true, None)?;
+    assert_eq!(config.patterns.len(), 2);
+    let config =
+      Config::load_merged(Some(PathBuf::from("config.yml")), true, None)?;
+    assert_eq!(config.patterns.len(), 3);
+
+    // When the base config takes precedence, its patterns and severity win
+    // and the local config can only add to it
+    std::fs::write(".ssq.yml", format!("{local_config}severity: low\n"))?;
+    std::fs::write("config.yml", format!("{base_config}severity: high\n"))?;
+    let load = |precedence| {
+      Config::load_merged(Some(PathBuf::from("config.yml")), true, precedence)
+    };
+    let config = load(Some(ConfigPrecedence::Local))?;
+    assert_eq!(config.patterns["github"].regex, "gh[pat]-[0-9a-f]{40}");
+    assert_eq!(config.get_effective_severity(), Some(&SeverityLevel::Low));
+
+    let config = load(Some(ConfigPrecedence::Base))?;
+    assert_eq!(config.patterns["github"].regex, "[A-Za-z0-9]{40}");
+    assert_eq!(config.patterns["npm"].regex, "npm_[A-Za-z0-9]{64}");
+    assert_eq!(config.get_effective_severity(), Some(&SeverityLevel::High));
+    assert_eq!(config.ignore_patterns, Some(vec!["TEST_.*".to_string()]));
+    let warnings = config.check().warnings;
+    assert!(warnings.iter().any(|warning| {
+      warning.contains("'github'") && warning.contains("replaced by the base")
+    }));
+    assert!(warnings.iter().any(|warning| {
+      warning.contains("'ignore_patterns'") && warning.contains("is ignored")
+    }));
+
+    // The base config can ask for it, and the local config can't undo that
+    std::fs::write(
+      "config.yml",
+      format!("{base_config}config_precedence: base\n"),
+    )?;
+    std::fs::write(
+      ".ssq.yml",
+      format!("{local_config}config_precedence: local\n"),
+    )?;
+    let config = load(None)?;
+    assert_eq!(config.patterns["github"].regex, "[A-Za-z0-9]{40}");
+    let config = load(Some(ConfigPrecedence::Local))?;
+    assert_eq!(config.patterns["github"].regex, "gh[pat]-[0-9a-f]{40}");
+    assert_eq!("BASE".parse(), Ok(ConfigPrecedence::Base));
+    assert!("remote".parse::<ConfigPrecedence>().is_err());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_base_precedence_is_not_weakened() -> Result<()> {
+    let temp = TempDir::new()?;
+    let base_dir = temp.path().join("base");
+    let project = temp.path().join("project");
+    std::fs::create_dir(&base_dir)?;
+    std::fs::create_dir(&project)?;
+    std::fs::write(
+      base_dir.join("config.yml"),
+      r"
+config_precedence: base
+severity: medium
+max_file_size: 1000
+scan_extensions: [env, txt]
+patterns:
+  mytok:
+    regex: 'MYTOK_[A-Z]{10}'
+    severity: high
+ignore_paths:
+  - 'fixtures/*'
+",
+    )?;
+    let _cwd = CurrentDir::enter(&project)?;
+    std::env::set_var(paths::CONFIG_DIR_VAR, &base_dir);
+    let load = |local: &str| -> Result<Config, ConfigError> {
+      std::fs::write(LOCAL_CONFIG_FILE, local)?;
+      Config::load_merged(None, true, None)
+    };
+
+    // The local config can't opt out of a base config that takes
+    // precedence, only one that doesn't
+    let config = load("use_base_config: false\n")?;
+    assert!(config.patterns.contains_key("mytok"));
+    assert!(config.check().warnings[0].contains("'use_base_config'"));
+    let config = load("use_base_config: false\nconfig_precedence: local\n")?;
+    assert!(config.patterns.contains_key("mytok"));
+    let config =
+      Config::load_merged(None, true, Some(ConfigPrecedence::Local))?;
+    assert!(config.patterns.is_empty());
+
+    // Nor can it hide the base config's findings
+    let config = load("ignore_patterns:\n  - 'MYTOK_'\n")?;
+    assert_eq!(config.ignore_patterns, None);
+    let config = load("ignore_paths:\n  - '*.txt'\n")?;
+    assert_eq!(config.ignore_paths, Some(vec!["fixtures/*".to_string()]));
+    let config = load("allowed_values:\n  - MYTOK_ABCDEFGHIJ\n")?;
+    assert!(!config.is_allowed_value("MYTOK_ABCDEFGHIJ"));
+    assert!(config.check().warnings[0].contains("'allowed_values'"));
+
+    let config =
+      Config::load_merged(None, true, Some(ConfigPrecedence::Local))?;
+    assert!(config.is_allowed_value("MYTOK_ABCDEFGHIJ"));
+
+    // Nor skip files or findings the base config would scan and report
+    let config = load(
+      r"
+severity: high
+category_severity:
+  cloud: critical
+max_file_size: 5000
+scan_extensions: [env]
+binary_threshold: 0.01
+entropy:
+  threshold: 5.5
+",
+    )?;
+    assert_eq!(
+      config.get_effective_severity(),
+      Some(&SeverityLevel::Medium)
+    );
+    assert!(config.category_severity.is_empty());
+    assert_eq!(config.max_file_size, Some(1000));
+    assert_eq!(
+      config.scan_extensions,
+      Some(vec!["env".to_string(), "txt".to_string()])
+    );
+    assert_eq!(config.binary_threshold, None);
+    assert!(config.entropy.is_none());
+    let warnings = config.check().warnings;
+    for setting in [
+      "severity",
+      "category_severity",
+      "entropy",
+      "max_file_size",
+      "scan_extensions",
+      "binary_threshold",
+    ] {
+      assert!(
+        warnings
+          .iter()
+          .any(|warning| warning.contains(&format!("'{setting}'"))),
+        "{setting}"
+      );
+    }
+
+    std::env::remove_var(paths::CONFIG_DIR_VAR);
+    Ok(())
+  }
+
+  #[test]
+  fn test_no_base_config() -> Result<()> {
+    let temp = TempDir::new()?;
+    let base_dir = temp.path().join("base");
+    let project = temp.path().join("project");
+    std::fs::create_dir(&base_dir)?;
+    std::fs::create_dir(&project)?;
+    std::fs::write(
+      base_dir.join("config.yml"),
+      r"
+patterns:
+  base-tok:
+    regex: 'BASE_[A-Z]{10}'
+    severity: high
+",
+    )?;
+    let _cwd = CurrentDir::enter(&project)?;
+    std::env::set_var(paths::CONFIG_DIR_VAR, &base_dir);
+    std::fs::write(
+      LOCAL_CONFIG_FILE,
+      r"
+patterns:
+  local-tok:
+    regex: 'LOCAL_[A-Z]{10}'
+    severity: high
+",
+    )?;
+    let names = |use_base_config| -> Result<Vec<String>> {
+      let config = Config::load_with_path(None, use_base_config, None)?;
+      let mut names: Vec<_> = config.patterns.into_keys().collect();
+      names.sort();
+      Ok(names)
+    };
+
+    assert_eq!(names(true)?, ["base-tok", "local-tok"]);
+    // --no-base-config leaves only the local config's patterns
+    assert_eq!(names(false)?, ["local-tok"]);
+
+    std::env::remove_var(paths::CONFIG_DIR_VAR);
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_local_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = TempDir::new()?;
+    let _cwd = CurrentDir::enter(temp_dir.path())?;
+    std::fs::write(
+      LOCAL_CONFIG_FILE,
+      r"
+patterns:
+  mytok:
+    regex: 'MYTOK_[A-Z]{10}'
+    severity: high
+severity: hihg
+",
+    )?;
+
+    // Both a scan and `ssq validate` fail rather than dropping the local
+    // config's patterns
+    let err = Config::load_with_path(None, false, None)
+      .expect_err("a bad local severity should fail to load");
+    assert!(matches!(err, ConfigError::ParseError(_)), "{err}");
+    assert!(err.to_string().contains("hihg"), "{err}");
+    assert!(matches!(
+      Config::load_merged(None, false, None),
+      Err(ConfigError::ParseError(_))
+    ));
+
+    // Without a local config there's nothing to report
+    std::fs::remove_file(LOCAL_CONFIG_FILE)?;
+    assert!(Config::load_merged(None, false, None)?.patterns.is_empty());
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_pattern() -> Result<(), Box<dyn std::error::Error>> {
+    let _cwd = TEST_ENV.lock();
+    let mut temp = NamedTempFile::new()?;
+    write!(
+      temp,
+      r"
+patterns:
+  good:
+    regex: 'AKIA[0-9A-Z]{{16}}'
+    severity: high
+  broken:
+    regex: 'token=([a-z'
+    severity: high
+"
+    )?;
+
+    let err =
+      Config::load_with_path(Some(temp.path().to_path_buf()), true, None)
+        .expect_err("invalid regex should fail to load");
+    assert!(
+      matches!(err, ConfigError::InvalidPattern { ref name, .. } if name == "broken")
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_invalid_binary_threshold() -> Result<(), Box<dyn std::error::Error>> {
+    let _cwd = TEST_ENV.lock();
+    let mut temp = NamedTempFile::new()?;
+    writeln!(temp, "binary_threshold: 1.5")?;
+
+    let err =
+      Config::load_with_path(Some(temp.path().to_path_buf()), true, None)
+        .expect_err("out of range threshold should fail to load");
+    assert!(
+      matches!(err, ConfigError::InvalidValue { ref name, .. } if name == "binary_threshold")
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_reports_every_problem() {
+    let pattern = |regex: &str| Pattern {
+      regex: regex.to_string(),
+      severity: SeverityLevel::High,
+      ..Pattern::default()
+    };
+    let mut base = Config::default();
+    base.patterns.insert("aws".into(), pattern("AKIA.*"));
+    base
+      .patterns
+      .insert("broken".into(), pattern("token=([a-z"));
+    let mut local = Config::default();
+    local
+      .patterns
+      .insert("aws".into(), pattern("AKIA[0-9A-Z]{16}"));
+    local.patterns.insert(
+      "nearby".into(),
+      Pattern {
+        near: Some(ProximityRule {
+          regex: "password:(".into(),
+          lines: 1,
+        }),
+        ..pattern("value: (\\w+)")
+      },
+    );
+    base.merge_config(&local);
+
+    let check = base.check();
+    assert_eq!(check.errors.len(), 2, "{:?}", check.errors);
+    assert!(check.errors[0].contains("broken"));
+    assert!(check.errors[1].contains("nearby.near"));
+    assert_eq!(check.warnings.len(), 1);
+    assert!(check.warnings[0].contains("'aws'"));
+
+    assert!(Config::default().check().errors.is_empty());
+  }
+
+  #[test]
+  fn test_unknown_pattern_severity() {
+    let config = "patterns:\n  aws:\n    regex: 'AKIA.*'\n    severity: hihg\n";
+    let err = serde_yaml::from_str::<Config>(config).unwrap_err();
+    assert!(err.to_string().contains("hihg"), "{err}");
+
+    let config = "patterns:\n  aws:\n    regex: 'AKIA.*'\n    severity: High\n";
+    let config: Config = serde_yaml::from_str(config).unwrap();
+    assert_eq!(config.patterns["aws"].severity, SeverityLevel::High);
+
+    // The global and per-category thresholds are checked the same way
+    let err = serde_yaml::from_str::<Config>("severity: hihg\n").unwrap_err();
+    assert!(err.to_string().contains("hihg"), "{err}");
+    let config = "category_severity:\n  aws: meduim\n";
+    let err = serde_yaml::from_str::<Config>(config).unwrap_err();
+    assert!(err.to_string().contains("meduim"), "{err}");
+  }
+
+  #[test]
+  fn test_lint_broad_patterns() -> Result<()> {
+    let mut config = Config::default();
+    for (name, regex) in [
+      ("anchored", "^.*secret"),
+      ("dot_star", "(?i).*token=\\w+"),
+      ("empty", "(?:key)?"),
+      ("good", "AKIA[0-9A-Z]{16}"),
+    ] {
+      config.patterns.insert(
+        name.into(),
+        Pattern {
+          regex: regex.into(),
+          severity: "high".into(),
+          ..Pattern::default()
+        },
+      );
+    }
+
+    let warnings = config.validate()?;
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings[0].starts_with("Pattern 'dot_star' starts with"));
+    assert_eq!(warnings[1], "Pattern 'empty' matches an empty string");
+    assert_eq!(config.check().lints, warnings);
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_expand_env_vars() {
+    let lookup = |name: &str| {
+      (name == "HOME" || name == "ENV").then(|| format!("<{name}>"))
+    };
+    let mut config = Config {
+      ignore_paths: Some(vec![
+        "${HOME}/secrets-allowed/*".to_string(),
+        "${UNSET_VAR}/*".to_string(),
+        "${HOME".to_string(),
+      ]),
+      ignore_patterns: Some(vec!["${ENV}_TOKEN$".to_string()]),
+      ..Config::default()
+    };
+    config.expand_env_vars(lookup);
+
+    assert_eq!(
+      config.ignore_paths,
+      Some(vec![
+        "<HOME>/secrets-allowed/*".to_string(),
+        "${UNSET_VAR}/*".to_string(),
+        "${HOME".to_string(),
+      ])
+    );
+    assert_eq!(
+      config.ignore_patterns,
+      Some(vec!["<ENV>_TOKEN$".to_string()])
+    );
+  }
+
+  #[test]
+  fn test_rule_filter() {
+    let mut config = Config::default();
+    for name in ["aws", "github", "npm"] {
+      config.patterns.insert(
+        name.into(),
+        Pattern {
+          regex: name.into(),
+          severity: "high".into(),
+          ..Pattern::default()
+        },
+      );
+    }
+
+    let unknown =
+      config.set_rule_filter(vec!["aws".into(), "github".into()], vec![]);
+    assert!(unknown.is_empty());
+    assert!(config.is_rule_enabled("aws"));
+    assert!(!config.is_rule_enabled("npm"));
+
+    let unknown =
+      config.set_rule_filter(vec![], vec!["aws".into(), "nope".into()]);
+    assert_eq!(unknown, ["nope"]);
+    assert!(!config.is_rule_enabled("aws"));
+    assert!(config.is_rule_enabled("npm"));
+  }
+
+  #[test]
+  fn test_tag_filter() {
+    let mut config = Config::default();
+    for (name, tags) in [
+      ("aws", vec!["cloud"]),
+      ("ssn", vec!["pii"]),
+      ("both", vec!["cloud", "pii"]),
+    ] {
+      config.patterns.insert(
+        name.into(),
+        Pattern {
+          regex: name.into(),
+          severity: "high".into(),
+          tags: tags.into_iter().map(String::from).collect(),
+          ..Pattern::default()
+        },
+      );
+    }
+    config.entropy = Some(EntropyConfig::default());
+
+    let unknown = config.set_tag_filter(vec!["cloud".into()], vec![]);
+    assert!(unknown.is_empty());
+    assert!(config.is_rule_enabled("aws"));
+    assert!(config.is_rule_enabled("both"));
+    assert!(!config.is_rule_enabled("ssn"));
+    assert!(!config.is_rule_enabled(ENTROPY_PATTERN_NAME));
+
+    let unknown =
+      config.set_tag_filter(vec![], vec!["pii".into(), "nope".into()]);
+    assert_eq!(unknown, ["nope"]);
+    assert!(config.is_rule_enabled("aws"));
+    assert!(!config.is_rule_enabled("both"));
+    assert!(config.is_rule_enabled(ENTROPY_PATTERN_NAME));
+  }
+  #[test]
+  fn test_parse_file_formats() -> Result<()> {
+    let temp = TempDir::new()?;
+    let toml_path = temp.path().join("config.toml");
+    std::fs::write(
+      &toml_path,
+      r#"
+severity = "HIGH"
+
+[patterns.aws]
+regex = "AKIA[0-9A-Z]{16}"
+severity = "CRITICAL"
+"#,
+    )?;
+    let json_path = temp.path().join("config.json");
+    std::fs::write(
+      &json_path,
+      r#"{
+  "severity": "HIGH",
+  "patterns": {
+    "aws": { "regex": "AKIA[0-9A-Z]{16}", "severity": "CRITICAL" }
+  }
+}"#,
+    )?;
+
+    for path in [toml_path, json_path] {
+      let config = Config::parse_file(&path)?;
+      assert_eq!(config.severity, Some(SeverityLevel::High));
+      assert_eq!(config.patterns["aws"].severity, SeverityLevel::Critical);
+    }
+
+    std::fs::write(temp.path().join("bad.toml"), "severity = [")?;
+    assert!(matches!(
+      Config::parse_file(&temp.path().join("bad.toml")),
+      Err(ConfigError::TomlError(_))
+    ));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_category_severity() {
+    let pattern = |severity: &str, category: Option<&str>| Pattern {
+      regex: "x".into(),
+      severity: severity.into(),
+      category: category.map(Into::into),
+      ..Pattern::default()
+    };
+    let mut config = Config {
+      severity: Some(SeverityLevel::Medium),
+      computed_severity: Some(SeverityLevel::Medium),
+      category_severity: HashMap::from([
+        ("aws".into(), "HIGH".into()),
+        ("internal".into(), "LOW".into()),
+      ]),
+      ..Config::default()
+    };
+
+    assert!(!config.meets_severity(&pattern("MEDIUM", Some("aws"))));
+    assert!(config.meets_severity(&pattern("HIGH", Some("aws"))));
+    assert!(config.meets_severity(&pattern("LOW", Some("internal"))));
+    assert!(!config.meets_severity(&pattern("LOW", Some("other"))));
+    assert!(!config.meets_severity(&pattern("LOW", None)));
+
+    // An explicit --severity still applies to every category
+    config.set_severity_filter("critical");
+    assert!(!config.meets_severity(&pattern("HIGH", Some("aws"))));
+    assert!(!config.meets_severity(&pattern("LOW", Some("internal"))));
+  }
+
+  #[test]
+  fn test_find_local_config() -> Result<()> {
+    let temp = TempDir::new()?;
+    let repo = temp.path().join("repo");
+    let nested = repo.join("src/nested");
+    std::fs::create_dir_all(&nested)?;
+    std::fs::create_dir(repo.join(".git"))?;
+
+    // Configs outside the repository are never used
+    std::fs::write(temp.path().join(LOCAL_CONFIG_FILE), "")?;
+    assert_eq!(Config::find_local_config(&nested), None);
+
+    std::fs::write(repo.join(LOCAL_CONFIG_FILE), "")?;
+    assert_eq!(
+      Config::find_local_config(&nested),
+      Some(repo.join(LOCAL_CONFIG_FILE))
+    );
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_scaffold_round_trips() -> Result<()> {
+    let config: Config = serde_yaml::from_str(&Config::scaffold()?)?;
+    config.validate()?;
+    assert_eq!(config.patterns.len(), 2);
+    assert_eq!(config.severity, Some(SeverityLevel::Low));
+    assert_eq!(
+      config.ignore_paths,
+      Some(vec!["**/fixtures/**".to_string()])
+    );
+
     Ok(())
   }
 }
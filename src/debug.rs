@@ -1,5 +1,68 @@
+//! Leveled diagnostics on stderr, set with `--log-level` or `RUST_LOG`
+
+use tracing_subscriber::EnvFilter;
+
+/// Log `msg` at the debug level
 pub fn debug(msg: &str) {
-  if std::env::var("DEBUG").is_ok() {
-    eprintln!("DEBUG: {msg}");
+  tracing::debug!("{msg}");
+}
+
+/// Start logging to stderr. `level` from `--log-level` wins over `RUST_LOG`,
+/// which wins over the older `DEBUG` variable. A plain level such as `trace`
+/// only applies to the scanner itself; a full filter such as
+/// `secret_squirrel::scan=trace,grep=debug` is used as written.
+pub fn init(level: Option<&str>) -> anyhow::Result<()> {
+  let directives = directives(
+    level,
+    std::env::var("RUST_LOG").ok(),
+    std::env::var_os("DEBUG").is_some(),
+  );
+  let filter = EnvFilter::try_new(&directives)
+    .map_err(|e| anyhow::anyhow!("Invalid log level {directives:?}: {e}"))?;
+  tracing_subscriber::fmt()
+    .with_env_filter(filter)
+    .with_writer(std::io::stderr)
+    .with_ansi(console::colors_enabled_stderr())
+    .init();
+  Ok(())
+}
+
+const LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+fn directives(
+  level: Option<&str>,
+  rust_log: Option<String>,
+  legacy_debug: bool,
+) -> String {
+  match (level, rust_log) {
+    // A bare level is meant for the scanner, not its dependencies
+    (Some(level), _) if LEVELS.contains(&level) => {
+      format!("warn,secret_squirrel={level}")
+    }
+    (Some(filter), _) => filter.to_string(),
+    (None, Some(rust_log)) if !rust_log.is_empty() => rust_log,
+    _ if legacy_debug => "warn,secret_squirrel=debug".to_string(),
+    _ => "warn".to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_log_directives() {
+    assert_eq!(directives(None, None, false), "warn");
+    assert_eq!(directives(None, None, true), "warn,secret_squirrel=debug");
+    assert_eq!(
+      directives(None, Some(String::new()), true),
+      "warn,secret_squirrel=debug"
+    );
+    assert_eq!(directives(None, Some("info".into()), true), "info");
+    assert_eq!(
+      directives(Some("trace"), Some("info".into()), true),
+      "warn,secret_squirrel=trace"
+    );
+    assert_eq!(directives(Some("grep=trace"), None, false), "grep=trace");
   }
 }
@@ -0,0 +1,517 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+  let output = Command::new("git")
+    .arg("-C")
+    .arg(repo)
+    .args(args)
+    .output()
+    .context("Failed to run git")?;
+
+  if !output.status.success() {
+    bail!(
+      "git {} failed: {}",
+      args.join(" "),
+      String::from_utf8_lossy(&output.stderr).trim()
+    );
+  }
+
+  Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the top-level directory of the git repository containing `path`
+pub fn toplevel(path: &Path) -> Result<PathBuf> {
+  git(path, &["rev-parse", "--show-toplevel"])
+    .map(|out| PathBuf::from(out.trim()))
+    .map_err(|_| anyhow!("Not a git repository: {}", path.display()))
+}
+
+/// Returns the files staged for commit (added, copied or modified), as
+/// absolute paths. Deleted files are excluded.
+pub fn staged_files(path: &Path) -> Result<Vec<PathBuf>> {
+  let root = toplevel(path)?;
+  let out = git(
+    &root,
+    &["diff", "--cached", "--name-only", "--diff-filter=ACM", "-z"],
+  )?;
+
+  Ok(
+    out
+      .split('\0')
+      .filter(|name| !name.is_empty())
+      .map(|name| root.join(name))
+      .collect(),
+  )
+}
+
+/// Resolves `rev` (a branch, tag or SHA) to the SHA of its commit in the
+/// repository at `root`
+pub fn resolve_commit(root: &Path, rev: &str) -> Result<String> {
+  let commit = format!("{rev}^{{commit}}");
+  git(
+    root,
+    &["rev-parse", "--verify", "--end-of-options", &commit],
+  )
+  .map(|out| out.trim().to_string())
+  .map_err(|_| anyhow!("Unknown revision: {rev}"))
+}
+
+/// A file in a commit's tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeFile {
+  /// Path relative to the repository root
+  pub path: PathBuf,
+  /// The blob's object ID
+  pub oid: String,
+  pub size: u64,
+}
+
+/// Lists the files in the tree of `commit`, leaving out symlinks and
+/// submodules
+pub fn tree_files(root: &Path, commit: &str) -> Result<Vec<TreeFile>> {
+  Ok(parse_tree(&git(
+    root,
+    &["ls-tree", "-r", "-z", "--long", "--full-tree", commit],
+  )?))
+}
+
+/// Parses `git ls-tree -r -z --long` output, whose entries are
+/// `<mode> <type> <oid> <size>\t<path>`, each ended by a NUL
+fn parse_tree(out: &str) -> Vec<TreeFile> {
+  out
+    .split('\0')
+    .filter_map(|entry| {
+      let (info, path) = entry.split_once('\t')?;
+      let mut info = info.split_whitespace();
+      let (mode, kind, oid) = (info.next()?, info.next()?, info.next()?);
+      // 120000 is a symlink, whose blob is only the target's path
+      if kind != "blob" || mode == "120000" {
+        return None;
+      }
+      Some(TreeFile {
+        path: PathBuf::from(path),
+        oid: oid.to_string(),
+        size: info.next()?.parse().ok()?,
+      })
+    })
+    .collect()
+}
+
+/// Reads each of `oids` from the object store of the repository at `root`
+/// with a single `git cat-file --batch`, calling `f` with its index and
+/// contents. Returning `false` from `f` stops reading.
+pub fn for_each_blob<F>(root: &Path, oids: &[&str], mut f: F) -> Result<()>
+where
+  F: FnMut(usize, &[u8]) -> bool,
+{
+  let mut child = Command::new("git")
+    .arg("-C")
+    .arg(root)
+    .args(["cat-file", "--batch"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .context("Failed to run git")?;
+  let mut stdin = child.stdin.take().context("Failed to write to git")?;
+  let stdout = child.stdout.take().context("Failed to read git output")?;
+
+  // Requests are written from another thread so a full output pipe can't
+  // stall them. Writing fails harmlessly if reading stops early.
+  let requests = oids.join("\n") + "\n";
+  let writer = std::thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+  let mut reader = BufReader::new(stdout);
+  let mut header = String::new();
+  let mut contents = Vec::new();
+  for i in 0..oids.len() {
+    header.clear();
+    if reader.read_line(&mut header)? == 0 {
+      break;
+    }
+    // `<oid> <type> <size>`, or `<oid> missing`
+    let size: usize = header
+      .split_whitespace()
+      .nth(2)
+      .and_then(|size| size.parse().ok())
+      .ok_or_else(|| anyhow!("Can't read object {}", header.trim()))?;
+    contents.resize(size + 1, 0);
+    reader.read_exact(&mut contents)?;
+    if !f(i, &contents[..size]) {
+      break;
+    }
+  }
+
+  let _ = child.kill();
+  let _ = writer.join();
+  child.wait()?;
+  Ok(())
+}
+
+/// The commit that last changed a line, from `git blame`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blame {
+  pub sha: String,
+  pub author: String,
+}
+
+/// Blames `lines` of `file` with a single `git blame`, keyed by line number.
+/// Lines that aren't committed yet are left out.
+pub fn blame(file: &Path, lines: &[u64]) -> Result<HashMap<u64, Blame>> {
+  let dir = file
+    .parent()
+    .filter(|dir| !dir.as_os_str().is_empty())
+    .unwrap_or(Path::new("."));
+  let name = file
+    .file_name()
+    .and_then(|name| name.to_str())
+    .ok_or_else(|| anyhow!("Can't blame {}", file.display()))?;
+
+  let ranges: Vec<String> = lines.iter().map(|n| format!("{n},{n}")).collect();
+  let mut args = vec!["blame", "--porcelain"];
+  for range in &ranges {
+    args.extend(["-L", range]);
+  }
+  args.extend(["--", name]);
+
+  Ok(parse_blame(&git(dir, &args)?))
+}
+
+/// Parses `git blame --porcelain` output. Each entry starts with a
+/// `<sha> <original line> <final line>` header, followed by the commit's
+/// details the first time it appears, and ends with the tab-prefixed line.
+fn parse_blame(out: &str) -> HashMap<u64, Blame> {
+  let mut authors: HashMap<&str, &str> = HashMap::new();
+  let mut blamed = HashMap::new();
+  let mut entry: Option<(&str, u64)> = None;
+
+  for line in out.lines() {
+    if line.starts_with('\t') {
+      if let Some((sha, line_number)) = entry.take() {
+        let uncommitted = sha.bytes().all(|b| b == b'0');
+        if let Some(author) = authors.get(sha).filter(|_| !uncommitted) {
+          blamed.insert(
+            line_number,
+            Blame {
+              sha: sha.to_string(),
+              author: (*author).to_string(),
+            },
+          );
+        }
+      }
+    } else if let Some((sha, _)) = entry {
+      if let Some(author) = line.strip_prefix("author ") {
+        authors.insert(sha, author);
+      }
+    } else {
+      let mut parts = line.split_whitespace();
+      if let (Some(sha), Some(line_number)) =
+        (parts.next(), parts.nth(1).and_then(|n| n.parse().ok()))
+      {
+        entry = Some((sha, line_number));
+      }
+    }
+  }
+
+  blamed
+}
+
+/// Commit metadata attached to lines found while walking history
+#[derive(Debug, Clone, Default)]
+pub struct Commit {
+  pub sha: String,
+  pub author: String,
+  pub date: String,
+}
+
+// Record separator marks our commit header lines, which can't collide with
+// diff output
+const COMMIT_MARKER: &str = "\x1ecommit ";
+
+/// Walks `git log -p` for the repository at `path`, calling `f` with every
+/// line added by each commit. When `since` is given, only commits in
+/// `since..HEAD` are visited, and when `after` is given, only commits made
+/// after that time. Returning `false` from `f` stops the walk.
+pub fn for_each_added_line<F>(
+  path: &Path,
+  since: Option<&str>,
+  after: Option<SystemTime>,
+  f: F,
+) -> Result<()>
+where
+  F: FnMut(Option<&Commit>, &Path, u64, &str) -> bool,
+{
+  let root = toplevel(path)?;
+  let range = since.map(|rev| format!("{rev}..HEAD"));
+  // `@<seconds>` is git's unambiguous spelling of a Unix timestamp
+  let cutoff = after.map(|time| {
+    let secs = time
+      .duration_since(UNIX_EPOCH)
+      .map_or(0, |elapsed| elapsed.as_secs());
+    format!("--since=@{secs}")
+  });
+  let mut args = vec![
+    "log",
+    "-p",
+    "--no-color",
+    "--no-ext-diff",
+    "--unified=0",
+    "--src-prefix=a/",
+    "--dst-prefix=b/",
+    "--format=%x1ecommit %H%x1f%an%x1f%aI",
+  ];
+  if let Some(ref cutoff) = cutoff {
+    args.push(cutoff);
+  }
+  if let Some(ref range) = range {
+    args.push(range);
+  }
+
+  walk_added_lines(&root, &args, f)
+}
+
+/// Calls `f` with every line added between the merge base of `rev` and
+/// `HEAD` and `HEAD` itself (`git diff rev...HEAD`), as a pull request would
+/// show it. No commit is passed to `f`. Returning `false` stops the walk.
+pub fn for_each_diff_line<F>(path: &Path, rev: &str, f: F) -> Result<()>
+where
+  F: FnMut(Option<&Commit>, &Path, u64, &str) -> bool,
+{
+  let root = toplevel(path)?;
+  let range = format!("{rev}...HEAD");
+  walk_added_lines(
+    &root,
+    &[
+      "diff",
+      "--no-color",
+      "--no-ext-diff",
+      "--unified=0",
+      "--src-prefix=a/",
+      "--dst-prefix=b/",
+      &range,
+    ],
+    f,
+  )
+}
+
+/// Streams the patch output of `git args` in `root`, calling `f` with each
+/// added line, its file and its line number in the new version. The commit
+/// is set once a commit header (see `COMMIT_MARKER`) has been seen. `args`
+/// must ask for the `a/` and `b/` prefixes, which a user's `diff.noprefix`
+/// would otherwise drop.
+fn walk_added_lines<F>(root: &Path, args: &[&str], mut f: F) -> Result<()>
+where
+  F: FnMut(Option<&Commit>, &Path, u64, &str) -> bool,
+{
+  let mut child = Command::new("git")
+    .arg("-C")
+    .arg(root)
+    // Keep non-ASCII file names as they are, rather than octal escaped
+    .args(["-c", "core.quotePath=false"])
+    .args(args)
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .context("Failed to run git")?;
+  let stdout = child.stdout.take().context("Failed to read git output")?;
+  let mut reader = BufReader::new(stdout);
+
+  let mut commit = None;
+  let mut file: Option<PathBuf> = None;
+  // Between a `diff --git` line and its first hunk, where `+++ ` names the
+  // file rather than adding a line that happens to start with `++ `
+  let mut in_file_header = false;
+  let mut line_number = 0u64;
+  let mut buf = Vec::new();
+
+  while reader.read_until(b'\n', &mut buf)? > 0 {
+    let raw = String::from_utf8_lossy(&buf);
+    let line = raw.trim_end_matches(['\n', '\r']);
+
+    if let Some(header) = line.strip_prefix(COMMIT_MARKER) {
+      let mut parts = header.splitn(3, '\x1f');
+      commit = Some(Commit {
+        sha: parts.next().unwrap_or_default().to_string(),
+        author: parts.next().unwrap_or_default().to_string(),
+        date: parts.next().unwrap_or_default().to_string(),
+      });
+      file = None;
+      in_file_header = false;
+    } else if line.starts_with("diff --git ") {
+      file = None;
+      in_file_header = true;
+    } else if in_file_header {
+      if let Some(name) = line.strip_prefix("+++ ") {
+        // Deleted files show up as /dev/null and have no added lines
+        file = parse_new_path(name).map(|name| root.join(name));
+      } else if let Some(hunk) = line.strip_prefix("@@ ") {
+        line_number = parse_hunk_start(hunk).unwrap_or(0);
+        in_file_header = false;
+      }
+    } else if let Some(hunk) = line.strip_prefix("@@ ") {
+      line_number = parse_hunk_start(hunk).unwrap_or(0);
+    } else if let Some(added) = line.strip_prefix('+') {
+      if let Some(ref file) = file {
+        if !f(commit.as_ref(), file, line_number, added) {
+          break;
+        }
+        line_number += 1;
+      }
+    }
+
+    buf.clear();
+  }
+
+  // The walk may have stopped early, so don't wait on a full pipe
+  let _ = child.kill();
+  let output = child.wait_with_output()?;
+  if !output.status.success() && output.status.code().is_some() {
+    bail!(
+      "git {} failed: {}",
+      args[0],
+      String::from_utf8_lossy(&output.stderr).trim()
+    );
+  }
+
+  Ok(())
+}
+
+/// Parses the file name from a `+++ b/<path>` line, without the `+++ `.
+/// Names git had to quote, such as ones with a tab or quote in them, are
+/// unquoted, and `/dev/null` gives `None`.
+fn parse_new_path(name: &str) -> Option<String> {
+  // git ends names containing a space with a tab
+  let name = name.strip_suffix('\t').unwrap_or(name);
+  let name = match name.strip_prefix('"').and_then(|n| n.strip_suffix('"')) {
+    Some(quoted) => unquote(quoted),
+    None => name.to_string(),
+  };
+  name.strip_prefix("b/").map(str::to_string)
+}
+
+/// Undoes git's C-style quoting of a path: backslash escapes for control
+/// characters, quotes and backslashes, and octal escapes for other bytes
+fn unquote(quoted: &str) -> String {
+  let mut bytes = Vec::with_capacity(quoted.len());
+  let mut rest = quoted.as_bytes();
+  while let Some((&byte, tail)) = rest.split_first() {
+    rest = tail;
+    if byte != b'\\' {
+      bytes.push(byte);
+      continue;
+    }
+    let Some((&escaped, tail)) = rest.split_first() else {
+      bytes.push(byte);
+      break;
+    };
+    rest = tail;
+    let octal = |b: &u8| (b'0'..=b'7').contains(b);
+    bytes.push(match escaped {
+      b'a' => 0x07,
+      b'b' => 0x08,
+      b't' => b'\t',
+      b'n' => b'\n',
+      b'v' => 0x0b,
+      b'f' => 0x0c,
+      b'r' => b'\r',
+      b'0'..=b'3' if rest.len() >= 2 && rest[..2].iter().all(octal) => {
+        let value =
+          (escaped - b'0') << 6 | (rest[0] - b'0') << 3 | (rest[1] - b'0');
+        rest = &rest[2..];
+        value
+      }
+      other => other,
+    });
+  }
+  String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Parses the new-file start line from a hunk header such as
+/// `-12,3 +14,5 @@`
+fn parse_hunk_start(hunk: &str) -> Option<u64> {
+  let new_range = hunk.split_whitespace().find(|s| s.starts_with('+'))?;
+  new_range[1..].split(',').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_new_path() {
+    assert_eq!(parse_new_path("b/app.env").as_deref(), Some("app.env"));
+    assert_eq!(
+      parse_new_path("b/my app.env\t").as_deref(),
+      Some("my app.env")
+    );
+    assert_eq!(
+      parse_new_path(r#""b/caf\303\251.env""#).as_deref(),
+      Some("café.env")
+    );
+    assert_eq!(
+      parse_new_path(r#""b/tab\there \"quoted\" back\\slash""#).as_deref(),
+      Some("tab\there \"quoted\" back\\slash")
+    );
+    assert_eq!(parse_new_path("/dev/null"), None);
+  }
+
+  #[test]
+  fn test_parse_blame() {
+    let sha = "a".repeat(40);
+    let uncommitted = "0".repeat(40);
+    let out = format!(
+      "{sha} 3 4 1\nauthor Ada Lovelace\nauthor-mail <ada@example.com>\n\
+       summary Add config\nfilename app.env\n\tKEY=one\n\
+       {sha} 8 9 1\nfilename app.env\n\tKEY=two\n\
+       {uncommitted} 12 12 1\nauthor Not Committed Yet\n\tKEY=three\n"
+    );
+
+    let blamed = parse_blame(&out);
+    assert_eq!(blamed.len(), 2);
+    let ada = Blame {
+      sha: sha.clone(),
+      author: "Ada Lovelace".into(),
+    };
+    assert_eq!(blamed[&4], ada);
+    assert_eq!(blamed[&9], ada);
+  }
+
+  #[test]
+  fn test_parse_tree() {
+    let oid = "b".repeat(40);
+    let out = format!(
+      "100644 blob {oid}     12\tconfig/app.env\0\
+       120000 blob {oid}      6\tlink.env\0\
+       160000 commit {oid}       -\tvendor/lib\0\
+       100755 blob {oid}   2048\tbin/deploy\tscript\0"
+    );
+
+    assert_eq!(
+      parse_tree(&out),
+      [
+        TreeFile {
+          path: "config/app.env".into(),
+          oid: oid.clone(),
+          size: 12,
+        },
+        TreeFile {
+          path: "bin/deploy\tscript".into(),
+          oid,
+          size: 2048,
+        },
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_hunk_start() {
+    assert_eq!(parse_hunk_start("-12,3 +14,5 @@"), Some(14));
+    assert_eq!(parse_hunk_start("-0,0 +1 @@ fn main()"), Some(1));
+    assert_eq!(parse_hunk_start("garbage"), None);
+  }
+}
@@ -0,0 +1,68 @@
+pub mod baseline;
+pub mod config;
+pub mod debug;
+mod git;
+mod paths;
+pub mod progress;
+pub mod scan;
+pub mod triage;
+pub mod ui;
+
+pub use config::{Config, Pattern};
+pub use progress::{ProgressReporter, SilentReporter};
+pub use scan::{Match, Scanner};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use parking_lot::Mutex;
+  use std::sync::{atomic::AtomicBool, Arc};
+  use tempfile::TempDir;
+
+  /// Progress from outside the crate, instead of the TUI
+  #[derive(Default)]
+  struct Problems(Mutex<Vec<String>>);
+
+  impl ProgressReporter for Arc<Problems> {
+    fn update(&self, _path: &str, _msg: &str, _pct: f32) {}
+
+    fn problem(&self, path: &str) {
+      self.0.lock().push(path.to_string());
+    }
+
+    fn complete(&self, _path: &str) {}
+  }
+
+  #[test]
+  fn test_embedded_scan() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    std::fs::write(temp.path().join("app.env"), "API_KEY=abc123\n")?;
+    let mut config = Config::default();
+    config.patterns.insert(
+      "api-key".into(),
+      Pattern {
+        regex: "API_KEY=([a-z0-9]+)".into(),
+        severity: "HIGH".into(),
+        ..Pattern::default()
+      },
+    );
+
+    let problems = Arc::new(Problems::default());
+    let mut scanner = Scanner::new(&config, Arc::new(AtomicBool::new(true)));
+    scanner.set_reporter(Box::new(problems.clone()));
+    scanner.scan_path(temp.path())?;
+
+    let found: Vec<_> = scanner
+      .matches()
+      .iter()
+      .map(|m: &Match| {
+        (m.pattern_name.as_str(), m.secret.as_str(), m.line_number)
+      })
+      .collect();
+    assert_eq!(found, [("api-key", "abc123", 1)]);
+    assert_eq!(problems.0.lock().len(), 1);
+    assert!(problems.0.lock()[0].ends_with("app.env"));
+
+    Ok(())
+  }
+}
@@ -1,26 +1,95 @@
-mod config;
-mod debug;
-mod paths;
-mod scan;
-mod ui;
-
 use anyhow::Result;
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{Parser, Subcommand, ValueEnum};
+use secret_squirrel::config::SeverityLevel;
+use secret_squirrel::scan::template::MatchTemplate;
+use secret_squirrel::triage::{self, Decision};
+use secret_squirrel::{baseline, config, debug, scan, ui};
+use std::io::Write;
+use std::num::{NonZeroU64, NonZeroUsize};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+  Human,
+  Json,
+  Sarif,
+  Junit,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Write a starter .ssq.yml in the current directory
+  Init {
+    /// Overwrite an existing .ssq.yml
+    #[arg(long)]
+    force: bool,
+  },
+  /// Check the merged config for errors without scanning, exiting non-zero
+  /// if there are any
+  Validate,
+  /// Try a pattern regex against sample text, highlighting what it reports
+  TestPattern {
+    /// Regex to test, written as it would appear in a config file
+    #[arg(long)]
+    regex: String,
+
+    /// File to read sample text from, or - for stdin
+    #[arg(long, default_value = "-")]
+    input: PathBuf,
+  },
+}
 
 #[derive(Parser)]
 #[command(name = "ssq")]
 #[command(about = "Secret Squirrel - Find potential secrets in your code")]
 struct Cli {
+  #[command(subcommand)]
+  command: Option<Command>,
+
   /// Override default config file location
   #[arg(long, global = true)]
   config: Option<PathBuf>,
 
-  /// Path to repository (defaults to current directory)
+  /// Skip the built-in base config, using only the local .ssq.yml (or
+  /// --config)
+  #[arg(long, global = true)]
+  no_base_config: bool,
+
+  /// Which config wins where the base and local configs both set
+  /// something: local (default) or base, so a local config can add to the
+  /// base config's rules but not weaken them. Overrides config_precedence
+  /// in the configs.
+  #[arg(long, global = true, value_name = "PRECEDENCE")]
+  config_precedence: Option<config::ConfigPrecedence>,
+
+  /// Fail on pattern lint warnings, such as a regex that matches an empty
+  /// string, instead of just printing them
+  #[arg(long, global = true)]
+  strict: bool,
+
+  /// Print without colors. Also set by a non-empty NO_COLOR environment
+  /// variable.
+  #[arg(long, global = true)]
+  no_color: bool,
+
+  /// How much to log to stderr: error, warn, info, debug or trace, or a
+  /// filter such as secret_squirrel::scan=trace. Overrides RUST_LOG.
+  #[arg(long, global = true, value_name = "LEVEL")]
+  log_level: Option<String>,
+
+  /// Paths to scan (defaults to current directory), or - for stdin. Git
+  /// modes take a single repository path.
   #[arg(default_value = ".")]
-  path: PathBuf,
+  paths: Vec<PathBuf>,
+
+  /// Scan content piped on stdin
+  #[arg(long, conflicts_with_all = ["staged", "history", "diff", "rev"])]
+  stdin: bool,
 
   /// Only scan staged files
   #[arg(long)]
@@ -30,13 +99,325 @@ struct Cli {
   #[arg(long)]
   history: bool,
 
+  /// Only scan history after this revision (requires --history)
+  #[arg(long, requires = "history")]
+  since: Option<String>,
+
+  /// Only scan history committed within this long of now, e.g. 30d, 12h or
+  /// 1w3d (requires --history). With --since, both limits apply.
+  #[arg(
+    long,
+    value_name = "DURATION",
+    value_parser = scan::parse_age,
+    requires = "history"
+  )]
+  since_duration: Option<Duration>,
+
+  /// Only scan lines added on HEAD since it diverged from this revision
+  /// (git diff REV...HEAD), e.g. origin/main
+  #[arg(long, value_name = "REV", conflicts_with_all = ["staged", "history"])]
+  diff: Option<String>,
+
+  /// Scan every file as it is at this revision, e.g. a release tag, read
+  /// from git without checking it out
+  #[arg(
+    long,
+    value_name = "REV",
+    conflicts_with_all = ["staged", "history", "diff"]
+  )]
+  rev: Option<String>,
+
   /// Print current configuration
   #[arg(long)]
   print_config: bool,
 
+  /// With --print-config, also print the exact regex compiled for each
+  /// pattern, after modifiers such as ignore_case are applied
+  #[arg(long, requires = "print_config")]
+  show_compiled: bool,
+
+  /// Print the files that would be scanned, after every ignore, glob, size
+  /// and binary check, without scanning them
+  #[arg(
+    long,
+    conflicts_with_all = ["stdin", "staged", "history", "diff", "rev"]
+  )]
+  list_files: bool,
+
   /// Only show patterns of this severity or higher
   #[arg(long, value_parser = ["low", "medium", "high", "critical"], ignore_case = true)]
   severity: Option<String>,
+
+  /// Exit non-zero if anything of this severity or higher is found, whether
+  /// or not --severity shows it
+  #[arg(long, value_name = "SEVERITY", value_parser = ["low", "medium", "high", "critical"], ignore_case = true)]
+  fail_on: Option<String>,
+
+  /// Mask matched secrets in the output
+  #[arg(long)]
+  redact: bool,
+
+  /// Suppress findings recorded in this baseline file
+  #[arg(long)]
+  baseline: Option<PathBuf>,
+
+  /// Record all current findings to the --baseline file
+  #[arg(long, requires = "baseline")]
+  write_baseline: bool,
+
+  /// Only run this rule (repeatable)
+  #[arg(long = "rule", value_name = "NAME")]
+  rules: Vec<String>,
+
+  /// Skip this rule (repeatable)
+  #[arg(long = "exclude-rule", value_name = "NAME")]
+  exclude_rules: Vec<String>,
+
+  /// Only run patterns with this tag (repeatable)
+  #[arg(long = "tag", value_name = "TAG")]
+  tags: Vec<String>,
+
+  /// Skip patterns with this tag (repeatable)
+  #[arg(long = "exclude-tag", value_name = "TAG")]
+  exclude_tags: Vec<String>,
+
+  /// Descend into symlinked directories. Beware that links can point
+  /// outside the repository, so this may scan (and report) unrelated files
+  #[arg(long)]
+  follow_symlinks: bool,
+
+  /// Only descend this many directories into each path; 1 scans just the
+  /// files directly inside it
+  #[arg(long, value_name = "N")]
+  depth: Option<NonZeroUsize>,
+
+  /// Only scan files matching this glob (repeatable)
+  #[arg(long, value_name = "GLOB")]
+  include: Vec<String>,
+
+  /// Skip files matching this glob (repeatable)
+  #[arg(long, value_name = "GLOB")]
+  exclude: Vec<String>,
+
+  /// Also scan files excluded by .gitignore, .git/info/exclude and the
+  /// global gitignore
+  #[arg(long)]
+  no_gitignore: bool,
+
+  /// Show this many lines of context around each match
+  #[arg(long, short = 'C', value_name = "N", default_value_t = 0)]
+  context: usize,
+
+  /// Stop recording matches for a file after this many
+  #[arg(long, value_name = "N")]
+  max_matches_per_file: Option<usize>,
+
+  /// Stop the scan once this many matches are found in total, and report
+  /// those as partial results
+  #[arg(long, value_name = "N")]
+  max_total_matches: Option<NonZeroUsize>,
+
+  /// Drop matches whose secret is shorter than this many characters, for
+  /// patterns without their own `min_match_length`
+  #[arg(long, value_name = "N")]
+  min_length: Option<NonZeroUsize>,
+
+  /// Skip files that no pattern matches with a single combined search
+  /// before running each pattern on its own
+  #[arg(long)]
+  fast: bool,
+
+  /// Scan files that look binary too
+  #[arg(long)]
+  scan_binary: bool,
+
+  /// Scan the files inside .zip, .tar and .tar.gz archives, reported as
+  /// archive.zip!path/inside. Archives inside archives aren't opened.
+  #[arg(
+    long,
+    conflicts_with_all = ["stdin", "staged", "history", "diff", "rev"]
+  )]
+  scan_archives: bool,
+
+  /// Scan with at most this many threads (default: one per CPU core)
+  #[arg(long, value_name = "N")]
+  threads: Option<NonZeroUsize>,
+
+  /// Stop scanning after this many seconds and report what was found so far
+  #[arg(long, value_name = "SECONDS")]
+  timeout: Option<NonZeroU64>,
+
+  /// Check whether matched credentials are live by asking the services that
+  /// issued them (patterns with `verify` only). Makes network requests.
+  #[arg(long)]
+  verify: bool,
+
+  /// Show who last changed each matched line, from git blame. Runs git once
+  /// per file with matches, so it can be slow on large result sets.
+  #[arg(long, conflicts_with_all = ["stdin", "history", "rev"])]
+  blame: bool,
+
+  /// Show paths relative to the scan root (the repository in git modes),
+  /// so reports are the same on every machine. On when scanning `.`
+  #[arg(long, overrides_with = "no_relative_paths")]
+  relative_paths: bool,
+
+  /// Show paths as they were reached, even when scanning `.`
+  #[arg(long, overrides_with = "relative_paths")]
+  no_relative_paths: bool,
+
+  /// Reuse matches from the last run for files that haven't changed, kept
+  /// in .ssq-cache in the current directory. The cache holds matched lines,
+  /// so keep it out of version control.
+  #[arg(long, overrides_with = "no_cache")]
+  cache: bool,
+
+  /// Scan every file, even with --cache earlier on the command line
+  #[arg(long, overrides_with = "cache")]
+  no_cache: bool,
+
+  /// Report every pattern's match separately, even on the same text
+  #[arg(long)]
+  no_dedupe: bool,
+
+  /// Print plain progress lines instead of the full-screen UI. This is the
+  /// default when stdout isn't a terminal
+  #[arg(long)]
+  no_tui: bool,
+
+  /// Draw progress below the prompt instead of full-screen, leaving the
+  /// final view in the scrollback
+  #[arg(long, conflicts_with = "no_tui")]
+  inline: bool,
+
+  /// Only print the final counts: no progress, banners or match details.
+  /// With --format json, output just the summary object
+  #[arg(long, short = 'q')]
+  quiet: bool,
+
+  /// Also list every file scanned, and those passed over as binary, too
+  /// large or unreadable
+  #[arg(long, short = 'v', conflicts_with = "quiet")]
+  verbose: bool,
+
+  /// Print each match as one line in this format instead of the detailed
+  /// results, e.g. '{file}:{line}:{pattern}'. Placeholders: {file},
+  /// {line}, {col}, {pattern}, {severity}, {match} and {desc}; \t is a tab
+  #[arg(long, value_name = "FORMAT", conflicts_with = "quiet")]
+  template: Option<MatchTemplate>,
+
+  /// After the scan, go through the matches one at a time, choosing to keep
+  /// each, ignore it with an ssq:ignore comment, add its value to
+  /// allowed_values in the local config, or add it to the --baseline file
+  #[arg(
+    long,
+    conflicts_with_all = ["quiet", "template", "output", "write_baseline"]
+  )]
+  interactive: bool,
+
+  /// Output format for results
+  #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+  format: OutputFormat,
+
+  /// Write results to this file instead of stdout
+  #[arg(long, short = 'o', value_name = "FILE")]
+  output: Option<PathBuf>,
+
+  /// After the scan, list the ignore_patterns and ignore_paths entries that
+  /// didn't apply to anything, so stale ones can be pruned
+  #[arg(long, conflicts_with = "cache")]
+  audit_ignores: bool,
+
+  /// Also write the summary object (as --format json --quiet prints it) to
+  /// this file, whatever the output format
+  #[arg(long, value_name = "FILE")]
+  summary_json: Option<PathBuf>,
+}
+
+/// Replace `path` with `contents` via a temporary sibling file, so readers
+/// never see a partially written report
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+  let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+  let tmp =
+    path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+  std::fs::write(&tmp, contents)
+    .and_then(|()| std::fs::rename(&tmp, path))
+    .inspect_err(|_| {
+      let _ = std::fs::remove_file(&tmp);
+    })
+    .map_err(|e| anyhow::anyhow!("Failed to write {}: {e}", path.display()))
+}
+
+fn init(force: bool) -> Result<()> {
+  let path = Path::new(config::LOCAL_CONFIG_FILE);
+  if path.exists() && !force {
+    anyhow::bail!(
+      "{} already exists; pass --force to overwrite it",
+      path.display()
+    );
+  }
+
+  std::fs::write(path, config::Config::scaffold()?)?;
+  println!("Wrote {}", path.display());
+  Ok(())
+}
+
+fn validate(
+  config_path: Option<PathBuf>,
+  use_base_config: bool,
+  precedence: Option<config::ConfigPrecedence>,
+  strict: bool,
+) -> Result<()> {
+  let config =
+    config::Config::load_merged(config_path, use_base_config, precedence)?;
+  let mut check = config.check();
+  if strict {
+    check.errors.append(&mut check.lints);
+  } else {
+    check.warnings.append(&mut check.lints);
+  }
+  for warning in &check.warnings {
+    eprintln!("{} {warning}", console::style("Warning:").yellow().bold());
+  }
+  for error in &check.errors {
+    eprintln!("{} {error}", console::style("Error:").red().bold());
+  }
+
+  if !check.errors.is_empty() {
+    anyhow::bail!("config has {} errors", check.errors.len());
+  }
+  println!("Config is valid ({} patterns)", config.patterns.len());
+  Ok(())
+}
+
+fn test_pattern(regex: &str, input: &Path, out: &mut impl Write) -> Result<()> {
+  let matcher = config::compile_regex("test-pattern", regex)?;
+  let text = if input.as_os_str() == "-" {
+    std::io::read_to_string(std::io::stdin())?
+  } else {
+    std::fs::read_to_string(input)?
+  };
+
+  let mut count = 0;
+  let mut total = 0;
+  for (i, line) in text.lines().enumerate() {
+    total += 1;
+    let Some((start, end)) = scan::secret_span(&matcher, line) else {
+      continue;
+    };
+    count += 1;
+    writeln!(
+      out,
+      "{}: {}{}{}",
+      console::style(i + 1).cyan(),
+      &line[..start],
+      console::style(&line[start..end]).red().bold().underlined(),
+      &line[end..]
+    )?;
+  }
+
+  writeln!(out, "\nMatched {count} of {total} lines")?;
+  Ok(())
 }
 
 fn run() -> Result<()> {
@@ -44,47 +425,380 @@ fn run() -> Result<()> {
   let r = running.clone();
 
   ctrlc::set_handler(move || {
-    r.store(false, Ordering::SeqCst);
-    // Clean up terminal state immediately
-    ui::ScanUI::cleanup();
-    println!("\nScan interrupted.");
-    std::process::exit(0);
+    // The scanner stops cooperatively and restores the terminal itself. A
+    // second Ctrl-C forces an immediate exit.
+    if !r.swap(false, Ordering::SeqCst) {
+      ui::ScanUI::cleanup();
+      std::process::exit(130);
+    }
   })?;
 
   let cli = Cli::parse();
-  let mut config = config::Config::load_with_path(cli.config)?;
+  if cli.no_color || std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+  {
+    console::set_colors_enabled(false);
+    console::set_colors_enabled_stderr(false);
+  }
+  debug::init(cli.log_level.as_deref())?;
+
+  match cli.command {
+    Some(Command::Init { force }) => return init(force),
+    Some(Command::Validate) => {
+      return validate(
+        cli.config,
+        !cli.no_base_config,
+        cli.config_precedence,
+        cli.strict,
+      );
+    }
+    Some(Command::TestPattern { regex, input }) => {
+      return test_pattern(&regex, &input, &mut std::io::stdout().lock());
+    }
+    None => {}
+  }
+
+  let mut config = config::Config::load_with_path(
+    cli.config,
+    !cli.no_base_config,
+    cli.config_precedence,
+  )?;
+
+  let lints = config.lints();
+  let level = if cli.strict {
+    console::style("Error:").red().bold()
+  } else {
+    console::style("Warning:").yellow().bold()
+  };
+  for lint in lints {
+    eprintln!("{level} {lint}");
+  }
+  if cli.strict && !lints.is_empty() {
+    anyhow::bail!("{} patterns failed lint checks", lints.len());
+  }
 
   // Apply severity filter if provided
-  if let Some(severity) = cli.severity {
-    config.set_severity_filter(&severity);
+  if let Some(ref severity) = cli.severity {
+    config.set_severity_filter(severity);
+  }
+
+  for name in config.set_rule_filter(cli.rules, cli.exclude_rules) {
+    eprintln!(
+      "{} unknown rule '{name}' matches no pattern",
+      console::style("Warning:").yellow().bold()
+    );
+  }
+  for tag in config.set_tag_filter(cli.tags, cli.exclude_tags) {
+    eprintln!(
+      "{} unknown tag '{tag}' matches no pattern",
+      console::style("Warning:").yellow().bold()
+    );
   }
 
   if cli.print_config {
-    config.print();
+    config.print(cli.show_compiled);
     return Ok(());
   }
 
-  println!("Scanning path: {}", cli.path.display());
-  if cli.staged {
-    println!("Scanning only staged files");
-    // TODO: Implement staged files scanning
+  // --severity only decides what's shown, so scan for anything --fail-on
+  // needs and hide the rest afterwards
+  let shown = cli.severity.as_deref().map(SeverityLevel::from);
+  let fail_on = cli.fail_on.as_deref().map(SeverityLevel::from);
+  if let (Some(shown), Some(fail_on)) = (&shown, &fail_on) {
+    if fail_on < shown {
+      config.set_severity_filter(cli.fail_on.as_deref().unwrap_or_default());
+    }
+  }
+
+  let stdin = cli.stdin || cli.paths.iter().any(|path| path.as_os_str() == "-");
+  let git_mode =
+    cli.staged || cli.history || cli.diff.is_some() || cli.rev.is_some();
+  if cli.paths.len() > 1 && (stdin || git_mode) {
+    anyhow::bail!(
+      "--stdin, --staged, --history, --diff and --rev take a single path, \
+       got {}",
+      cli.paths.len()
+    );
+  }
+  let repo = &cli.paths[0];
+  if cli.relative_paths && cli.paths.len() > 1 {
+    anyhow::bail!("--relative-paths needs a single path to be relative to");
+  }
+  if cli.template.is_some() && cli.format != OutputFormat::Human {
+    anyhow::bail!("--template only applies to the human format");
+  }
+  if cli.interactive {
+    if cli.format != OutputFormat::Human {
+      anyhow::bail!("--interactive only applies to the human format");
+    }
+    if !console::Term::stdout().is_term() {
+      anyhow::bail!("--interactive needs a terminal");
+    }
+  }
+
+  // Machine-readable formats own stdout and --quiet or --template want only
+  // their own lines, so skip the status lines and TUI
+  let show_status = cli.format == OutputFormat::Human
+    && !cli.quiet
+    && !cli.list_files
+    && cli.template.is_none();
+  if show_status && !stdin {
+    let paths: Vec<_> = cli
+      .paths
+      .iter()
+      .map(|path| path.display().to_string())
+      .collect();
+    println!("Scanning path: {}", paths.join(", "));
   }
-  if cli.history {
-    println!("Scanning git history");
-    // TODO: Implement git history scanning
+
+  let mut scanner = scan::Scanner::new(&config, running.clone());
+  // The TUI makes no sense for piped input
+  if show_status && !stdin {
+    let mut ui = ui::ScanUI::new(cli.no_tui, running.clone());
+    ui.set_inline(cli.inline);
+    scanner.set_reporter(Box::new(ui));
+  }
+  scanner.set_redact(cli.redact);
+  scanner.set_context(cli.context);
+  scanner.set_respect_gitignore(!cli.no_gitignore);
+  scanner.set_follow_symlinks(cli.follow_symlinks);
+  scanner.set_max_depth(cli.depth.map(NonZeroUsize::get));
+  scanner.set_max_matches_per_file(cli.max_matches_per_file);
+  scanner.set_max_total_matches(cli.max_total_matches.map(NonZeroUsize::get));
+  scanner.set_min_match_length(cli.min_length.map(NonZeroUsize::get));
+  scanner.set_fast(cli.fast);
+  scanner.set_scan_binary(cli.scan_binary);
+  scanner.set_scan_archives(cli.scan_archives);
+  scanner.set_threads(cli.threads.map(NonZeroUsize::get));
+  scanner.set_list_files(cli.verbose);
+  scanner.set_cache(cli.cache.then(|| PathBuf::from(scan::cache::CACHE_FILE)));
+  scanner.set_file_globs(&cli.include, &cli.exclude)?;
+  scanner.set_relative_paths(
+    cli.relative_paths
+      || (!cli.no_relative_paths && cli.paths == [PathBuf::from(".")]),
+  );
+  if cli.list_files {
+    if stdin {
+      anyhow::bail!("--list-files needs paths to walk, not stdin");
+    }
+    let files = scanner.files_to_scan(&cli.paths)?;
+    for file in &files {
+      println!("{}", file.display());
+    }
+    eprintln!("{} files would be scanned", files.len());
+    return Ok(());
+  }
+  let watchdog = cli.timeout.map(|seconds| {
+    spawn_watchdog(Duration::from_secs(seconds.get()), running.clone())
+  });
+  let result = if stdin {
+    scanner.scan_stdin()
+  } else if cli.history {
+    if show_status {
+      println!("Scanning git history");
+    }
+    scanner.scan_history(repo, cli.since.as_deref(), cli.since_duration)
+  } else if let Some(ref rev) = cli.diff {
+    if show_status {
+      println!("Scanning changes since {rev}");
+    }
+    scanner.scan_diff(repo, rev)
+  } else if let Some(ref rev) = cli.rev {
+    if show_status {
+      println!("Scanning files at {rev}");
+    }
+    scanner.scan_rev(repo, rev)
+  } else if cli.staged {
+    if show_status {
+      println!("Scanning only staged files");
+    }
+    scanner.scan_staged(repo)
+  } else {
+    scanner.scan_paths(&cli.paths)
+  };
+  for (file, reason) in scanner.forced_files() {
+    eprintln!(
+      "{} scanned {file} even though it's {reason}, because it was named \
+       explicitly",
+      console::style("Warning:").yellow().bold()
+    );
   }
 
-  let mut scanner = scan::Scanner::new(&config, running);
-  let result = scanner.scan_path(&cli.path);
+  let timed_out = watchdog.is_some_and(|(disarm, thread)| {
+    drop(disarm);
+    thread.join().unwrap_or(false)
+  });
 
-  // Only print results if we weren't interrupted
+  // Only print results if we weren't interrupted. A timeout or the total
+  // match limit still reports what was found, but says so.
+  if let Some(seconds) = cli.timeout.filter(|_| timed_out) {
+    eprintln!(
+      "\n{}",
+      console::style(format!(
+        "Scan timed out after {seconds}s, results are partial"
+      ))
+      .yellow()
+    );
+  } else if let Some(max) = cli
+    .max_total_matches
+    .filter(|_| scanner.reached_max_total_matches())
+  {
+    eprintln!(
+      "\n{}",
+      console::style(format!(
+        "Scan stopped after {max} matches, results are partial"
+      ))
+      .yellow()
+    );
+  } else if scanner.was_interrupted() {
+    eprintln!("\n{}", console::style("Scan interrupted.").yellow());
+    std::process::exit(130);
+  }
   if result.is_ok() {
-    scanner.print_results();
+    if let Some(ref path) = cli.baseline {
+      if cli.write_baseline {
+        let baseline = baseline::Baseline::from_matches(scanner.matches());
+        baseline.save(path)?;
+        eprintln!(
+          "Wrote {} findings to baseline {}",
+          baseline.len(),
+          path.display()
+        );
+        return result;
+      }
+
+      // Triage can start a baseline that doesn't exist yet
+      if !cli.interactive || path.exists() {
+        let baseline = baseline::Baseline::load(path)?;
+        let suppressed = scanner.apply_baseline(&baseline);
+        debug::debug(&format!("{suppressed} matches suppressed by baseline"));
+      }
+    }
+
+    if !cli.no_dedupe {
+      scanner.dedupe_matches();
+    }
+    scanner.sort_matches();
+    if cli.verify {
+      scanner.verify_matches();
+    }
+    if cli.blame {
+      scanner.blame_matches();
+    }
+
+    let severity = |m: &scan::Match| m.pattern.severity;
+    let mut failing = fail_on.as_ref().map_or(0, |fail_on| {
+      scanner
+        .matches()
+        .iter()
+        .filter(|m| severity(m) >= *fail_on)
+        .count()
+    });
+    if let Some(ref shown) = shown {
+      scanner.retain_matches(|m| severity(m) >= *shown);
+    }
+    if cli.interactive {
+      let term = console::Term::stdout();
+      let mut decisions =
+        triage::prompt(&scanner, &term, cli.baseline.is_some())?;
+      triage::apply(
+        &scanner,
+        &mut decisions,
+        cli.baseline.as_deref(),
+        &mut std::io::stdout(),
+      )?;
+      // Suppressed matches no longer count against --fail-on
+      let mut decisions = decisions.into_iter();
+      scanner.retain_matches(|m| {
+        let keep = decisions.next() == Some(Decision::Keep);
+        if !keep && fail_on.as_ref().is_some_and(|f| severity(m) >= *f) {
+          failing -= 1;
+        }
+        keep
+      });
+    }
+
+    if cli.output.is_some() {
+      // Keep terminal colors out of report files
+      console::set_colors_enabled(false);
+    }
+    let mut report = Vec::new();
+    match cli.format {
+      OutputFormat::Human if cli.quiet || cli.interactive => {
+        scanner.write_summary(&mut report)?;
+      }
+      OutputFormat::Human => match cli.template {
+        Some(ref template) => scanner.write_template(&mut report, template)?,
+        None => scanner.write_results(&mut report)?,
+      },
+      OutputFormat::Json if cli.quiet => {
+        writeln!(report, "{}", scanner.summary_as_json()?)?;
+      }
+      OutputFormat::Json => writeln!(report, "{}", scanner.results_as_json()?)?,
+      OutputFormat::Sarif => {
+        writeln!(report, "{}", scanner.results_as_sarif()?)?;
+      }
+      OutputFormat::Junit => write!(report, "{}", scanner.results_as_junit())?,
+    }
+    match cli.output {
+      Some(ref path) => write_atomic(path, &report)?,
+      None => std::io::stdout().write_all(&report)?,
+    }
+    if let Some(ref path) = cli.summary_json {
+      let summary = format!("{}\n", scanner.summary_as_json()?);
+      write_atomic(path, summary.as_bytes())?;
+    }
+    if cli.audit_ignores {
+      audit_ignores(&scanner.unused_ignores());
+    }
+
+    if failing > 0 {
+      let level = cli.fail_on.unwrap_or_default().to_uppercase();
+      anyhow::bail!("{failing} potential secrets at {level} or above");
+    }
   }
 
   result
 }
 
+/// Report the ignore rules that applied to nothing, on stderr so the
+/// results stay parseable
+fn audit_ignores(unused: &scan::UnusedIgnores) {
+  if unused.is_empty() {
+    eprintln!("Every ignore rule applied during the scan");
+    return;
+  }
+  for (key, rules) in [
+    ("ignore_patterns", &unused.ignore_patterns),
+    ("ignore_paths", &unused.ignore_paths),
+  ] {
+    for rule in rules {
+      eprintln!(
+        "{} {key} entry '{rule}' didn't apply to anything",
+        console::style("Unused:").yellow().bold()
+      );
+    }
+  }
+}
+
+/// Stop the scan after `timeout` by clearing `running`, as Ctrl-C does.
+/// Dropping the returned sender disarms it, and the thread returns whether
+/// it fired.
+fn spawn_watchdog(
+  timeout: Duration,
+  running: Arc<AtomicBool>,
+) -> (mpsc::Sender<()>, JoinHandle<bool>) {
+  let (disarm, disarmed) = mpsc::channel::<()>();
+  let thread = std::thread::spawn(move || {
+    let fired =
+      disarmed.recv_timeout(timeout) == Err(RecvTimeoutError::Timeout);
+    if fired {
+      running.store(false, Ordering::SeqCst);
+    }
+    fired
+  });
+  (disarm, thread)
+}
+
 fn main() {
   let hook = std::panic::take_hook();
   std::panic::set_hook(Box::new(move |info| {
@@ -98,3 +812,74 @@ fn main() {
     std::process::exit(1);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  /// Names in `dir` that look like `write_atomic`'s temporary files
+  fn temp_files(dir: &Path) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+      let name = entry?.file_name().to_string_lossy().into_owned();
+      if name.ends_with(".tmp") {
+        names.push(name);
+      }
+    }
+    Ok(names)
+  }
+
+  #[test]
+  fn test_test_pattern() -> Result<()> {
+    console::set_colors_enabled(true);
+    let temp = TempDir::new()?;
+    let input = temp.path().join("sample.env");
+    std::fs::write(&input, "API_KEY=abc123\nDEBUG=true\nAPI_KEY=\n")?;
+
+    // Only the capture group is highlighted
+    let mut out = Vec::new();
+    test_pattern("^API_KEY=([a-z0-9]+)$", &input, &mut out)?;
+    let secret = console::style("abc123").red().bold().underlined();
+    assert_eq!(
+      String::from_utf8(out)?,
+      format!(
+        "{}: API_KEY={secret}\n\nMatched 1 of 3 lines\n",
+        console::style(1).cyan()
+      )
+    );
+
+    // Reported as a scan with the same regex would report it
+    let err = test_pattern("API_KEY=(", &input, &mut Vec::new()).unwrap_err();
+    let pattern = config::Pattern {
+      regex: "API_KEY=(".into(),
+      ..config::Pattern::default()
+    };
+    let expected = pattern.matcher("test-pattern").err().unwrap();
+    assert_eq!(err.to_string(), expected.to_string());
+    assert!(err.to_string().starts_with("Invalid regex in pattern"));
+
+    Ok(())
+  }
+
+  #[test]
+  fn test_write_atomic() -> Result<()> {
+    let temp = TempDir::new()?;
+    let path = temp.path().join("report.json");
+    std::fs::write(&path, "old report")?;
+
+    write_atomic(&path, b"{\"matches\": []}\n")?;
+    assert_eq!(std::fs::read_to_string(&path)?, "{\"matches\": []}\n");
+    assert!(temp_files(temp.path())?.is_empty());
+
+    // A directory in the way fails the rename, and the temporary file goes
+    let blocked = temp.path().join("blocked");
+    std::fs::create_dir(&blocked)?;
+    let err = write_atomic(&blocked, b"report").unwrap_err();
+    assert!(err.to_string().contains("Failed to write"));
+    assert!(blocked.is_dir());
+    assert!(temp_files(temp.path())?.is_empty());
+
+    Ok(())
+  }
+}
@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn is_wsl() -> bool {
   std::fs::read_to_string("/proc/version")
@@ -6,25 +6,67 @@ fn is_wsl() -> bool {
     .unwrap_or(false)
 }
 
-/// Returns the config directory path based on platform:
+/// Overrides the config directory on every platform, e.g. for packagers and
+/// CI that need a fixed location
+pub const CONFIG_DIR_VAR: &str = "SSQ_CONFIG_DIR";
+
+/// Held by tests that change or depend on the process-wide environment or
+/// current directory, so they don't see each other's changes
+#[cfg(test)]
+pub(crate) static TEST_ENV: parking_lot::Mutex<()> =
+  parking_lot::Mutex::new(());
+
+/// Returns the config directory path: $SSQ_CONFIG_DIR when it's set,
+/// otherwise based on platform:
 /// - Windows (not WSL): %APPDATA%/secret-squirrel
-/// - macOS: ~/.config/secret-squirrel
-/// - Linux: ~/.config/secret-squirrel
-/// - WSL: ~/.config/secret-squirrel
+/// - macOS, Linux and WSL: $XDG_CONFIG_HOME/secret-squirrel, falling back to
+///   ~/.config/secret-squirrel when it's unset (or not an absolute path)
 pub fn get_config_dir() -> Option<PathBuf> {
-  if cfg!(windows) && !is_wsl() {
+  if let Some(dir) = std::env::var_os(CONFIG_DIR_VAR).filter(|d| !d.is_empty())
+  {
+    Some(PathBuf::from(dir))
+  } else if cfg!(windows) && !is_wsl() {
     // Windows-specific path (not in WSL)
     std::env::var("APPDATA")
       .ok()
       .map(|appdata| PathBuf::from(appdata).join("secret-squirrel"))
   } else {
     // Linux-style path for Linux, macOS, and WSL
-    std::env::var("HOME")
-      .ok()
-      .map(|home| PathBuf::from(home).join(".config").join("secret-squirrel"))
+    std::env::var_os("XDG_CONFIG_HOME")
+      .map(PathBuf::from)
+      .filter(|dir| dir.is_absolute())
+      .or_else(|| {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+      })
+      .map(|dir| dir.join("secret-squirrel"))
   }
 }
 
+/// Copy the base config at `src` to `config.yml` in `config_dir`, returning
+/// why it couldn't be when the directory is unknown or the copy fails
+#[allow(dead_code)] // Called by build.rs, which includes this file
+pub fn install_base_config(
+  src: &Path,
+  config_dir: Option<PathBuf>,
+) -> Result<PathBuf, String> {
+  let Some(config_dir) = config_dir else {
+    return Err(format!(
+      "Could not determine the config directory, so the base config wasn't \
+       installed; set {CONFIG_DIR_VAR} to choose one"
+    ));
+  };
+  let dest = config_dir.join("config.yml");
+  std::fs::create_dir_all(&config_dir)
+    .and_then(|()| std::fs::copy(src, &dest))
+    .map_err(|e| {
+      format!(
+        "Could not install the base config to {}: {e}",
+        dest.display()
+      )
+    })?;
+  Ok(dest)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -32,6 +74,7 @@ mod tests {
 
   #[test]
   fn test_windows_path() {
+    let _env = TEST_ENV.lock();
     if cfg!(windows) && !is_wsl() {
       env::set_var("APPDATA", r"C:\Users\test\AppData\Roaming");
       assert_eq!(
@@ -43,12 +86,89 @@ mod tests {
 
   #[test]
   fn test_unix_style_path() {
+    let _env = TEST_ENV.lock();
+    if !cfg!(windows) || is_wsl() {
+      env::set_var("HOME", "/home/user");
+      env::remove_var("XDG_CONFIG_HOME");
+      assert_eq!(
+        get_config_dir().unwrap(),
+        PathBuf::from("/home/user/.config/secret-squirrel")
+      );
+    }
+  }
+
+  #[test]
+  fn test_config_dir_override() {
+    let _env = TEST_ENV.lock();
+    env::set_var("HOME", "/home/user");
+    env::set_var("XDG_CONFIG_HOME", "/home/user/.xdg");
+    env::set_var("APPDATA", r"C:\Users\test\AppData\Roaming");
+
+    // Used as is, whatever the platform would pick
+    env::set_var(CONFIG_DIR_VAR, "/opt/ssq");
+    assert_eq!(get_config_dir().unwrap(), PathBuf::from("/opt/ssq"));
+
+    // Even without anything else to go on
+    env::remove_var("HOME");
+    env::remove_var("XDG_CONFIG_HOME");
+    env::remove_var("APPDATA");
+    assert_eq!(get_config_dir().unwrap(), PathBuf::from("/opt/ssq"));
+
+    // Ignored when empty
+    env::set_var(CONFIG_DIR_VAR, "");
+    assert_eq!(get_config_dir(), None);
+    env::remove_var(CONFIG_DIR_VAR);
+    env::set_var("HOME", "/home/user");
+  }
+
+  #[test]
+  fn test_xdg_config_home() {
+    let _env = TEST_ENV.lock();
     if !cfg!(windows) || is_wsl() {
+      env::set_var("XDG_CONFIG_HOME", "/home/user/.xdg");
+      assert_eq!(
+        get_config_dir().unwrap(),
+        PathBuf::from("/home/user/.xdg/secret-squirrel")
+      );
+
+      // It's checked before HOME, so works without it
+      env::remove_var("HOME");
+      assert_eq!(
+        get_config_dir().unwrap(),
+        PathBuf::from("/home/user/.xdg/secret-squirrel")
+      );
+
+      // Relative paths are invalid per the XDG spec
       env::set_var("HOME", "/home/user");
+      env::set_var("XDG_CONFIG_HOME", "relative");
       assert_eq!(
         get_config_dir().unwrap(),
         PathBuf::from("/home/user/.config/secret-squirrel")
       );
+      env::remove_var("XDG_CONFIG_HOME");
     }
   }
+
+  #[test]
+  fn test_install_base_config() -> std::io::Result<()> {
+    let temp = tempfile::TempDir::new()?;
+    let src = temp.path().join("ssq.yml");
+    std::fs::write(&src, "patterns: {}\n")?;
+
+    let dest = install_base_config(&src, Some(temp.path().join("config")))
+      .expect("installs into a new directory");
+    assert_eq!(dest, temp.path().join("config/config.yml"));
+    assert_eq!(std::fs::read_to_string(dest)?, "patterns: {}\n");
+
+    // Failures are reported for build.rs to warn about, not panics
+    let err = install_base_config(&src, None).unwrap_err();
+    assert!(err.contains(CONFIG_DIR_VAR), "{err}");
+    let err = install_base_config(&src, Some(src.clone())).unwrap_err();
+    assert!(
+      err.starts_with("Could not install the base config to"),
+      "{err}"
+    );
+
+    Ok(())
+  }
 }
@@ -0,0 +1,31 @@
+/// Receives progress events while a scan runs, so the scanner can drive a
+/// TUI, plain log lines, or nothing at all when embedded as a library.
+///
+/// Methods are called concurrently from the scanning threads.
+pub trait ProgressReporter: Send + Sync {
+  /// Called once the files to scan are known, before any are scanned
+  fn start(&self, _total_files: usize) {}
+
+  /// A file is being checked; `pct` is how far through it the scan is
+  fn update(&self, path: &str, msg: &str, pct: f32);
+
+  /// A potential secret was found in a file. Called once per match.
+  fn problem(&self, path: &str);
+
+  /// A file has been scanned
+  fn complete(&self, path: &str);
+
+  /// Called after the last file, before results are reported
+  fn finish(&self) {}
+}
+
+/// Discards all progress events. The scanner's default reporter.
+pub struct SilentReporter;
+
+impl ProgressReporter for SilentReporter {
+  fn update(&self, _path: &str, _msg: &str, _pct: f32) {}
+
+  fn problem(&self, _path: &str) {}
+
+  fn complete(&self, _path: &str) {}
+}
@@ -0,0 +1,138 @@
+//! Entries of zip and tar archives, so `--scan-archives` can search them as
+//! if they were files
+
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{Read, Result};
+use std::ops::ControlFlow;
+use std::path::Path;
+
+/// Separates an archive's path from an entry's path inside it in match
+/// labels, e.g. `build.zip!config/app.env`
+pub const ENTRY_SEPARATOR: char = '!';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+  Zip,
+  Tar,
+  TarGz,
+}
+
+impl ArchiveKind {
+  /// The kind of archive `path` names, going by its extension
+  pub fn of(path: &Path) -> Option<Self> {
+    let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+    if name.ends_with(".zip") {
+      Some(Self::Zip)
+    } else if name.ends_with(".tar") {
+      Some(Self::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+      Some(Self::TarGz)
+    } else {
+      None
+    }
+  }
+}
+
+/// Call `visit` with the name, size and contents of each regular file in the
+/// archive at `path`, until it breaks or fails. Directories, links and other
+/// special entries are left out.
+pub fn for_each_entry(
+  path: &Path,
+  kind: ArchiveKind,
+  mut visit: impl FnMut(&str, u64, &mut dyn Read) -> Result<ControlFlow<()>>,
+) -> Result<()> {
+  let file = File::open(path)?;
+  match kind {
+    ArchiveKind::Zip => {
+      let mut archive = zip::ZipArchive::new(file)?;
+      for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        if entry.is_dir() {
+          continue;
+        }
+        let name = entry.name().to_string();
+        let size = entry.size();
+        if visit(&name, size, &mut entry)?.is_break() {
+          break;
+        }
+      }
+      Ok(())
+    }
+    ArchiveKind::Tar => tar_entries(file, visit),
+    ArchiveKind::TarGz => tar_entries(GzDecoder::new(file), visit),
+  }
+}
+
+fn tar_entries(
+  reader: impl Read,
+  mut visit: impl FnMut(&str, u64, &mut dyn Read) -> Result<ControlFlow<()>>,
+) -> Result<()> {
+  let mut archive = tar::Archive::new(reader);
+  for entry in archive.entries()? {
+    let mut entry = entry?;
+    if !entry.header().entry_type().is_file() {
+      continue;
+    }
+    let name = entry.path()?.display().to_string();
+    let size = entry.size();
+    if visit(&name, size, &mut entry)?.is_break() {
+      break;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::Write;
+  use tempfile::TempDir;
+
+  fn entries(path: &Path) -> anyhow::Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for_each_entry(path, ArchiveKind::of(path).unwrap(), |name, _, reader| {
+      let mut contents = String::new();
+      reader.read_to_string(&mut contents)?;
+      entries.push((name.to_string(), contents));
+      Ok(ControlFlow::Continue(()))
+    })?;
+    Ok(entries)
+  }
+
+  #[test]
+  fn test_archive_entries() -> anyhow::Result<()> {
+    let temp = TempDir::new()?;
+    let expected = vec![("config/app.env".to_string(), "KEY=1\n".to_string())];
+
+    let zip_path = temp.path().join("bundle.ZIP");
+    let options = zip::write::SimpleFileOptions::default();
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path)?);
+    zip.add_directory("config/", options)?;
+    zip.start_file("config/app.env", options)?;
+    zip.write_all(b"KEY=1\n")?;
+    zip.finish()?;
+    assert_eq!(entries(&zip_path)?, expected);
+
+    let tgz_path = temp.path().join("bundle.tgz");
+    let gz = flate2::write::GzEncoder::new(
+      File::create(&tgz_path)?,
+      flate2::Compression::default(),
+    );
+    let mut tar = tar::Builder::new(gz);
+    let mut header = tar::Header::new_gnu();
+    header.set_size(6);
+    header.set_mode(0o644);
+    tar.append_data(&mut header, "config/app.env", &b"KEY=1\n"[..])?;
+    tar.into_inner()?.finish()?;
+    assert_eq!(entries(&tgz_path)?, expected);
+
+    assert_eq!(
+      ArchiveKind::of(Path::new("app.tar.gz")),
+      Some(ArchiveKind::TarGz)
+    );
+    assert_eq!(ArchiveKind::of(Path::new("app.gz")), None);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,167 @@
+//! Matches from earlier scans, so files that haven't changed since aren't
+//! scanned again with `--cache`
+
+use super::{FileSuppressions, Match};
+use crate::debug::debug;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Where the cache is kept, in the current directory. It holds matched
+/// lines, so it's never scanned itself.
+pub const CACHE_FILE: &str = ".ssq-cache";
+
+/// Cached matches per file, valid only for the settings hashed into `key`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScanCache {
+  key: String,
+  files: HashMap<String, CachedFile>,
+}
+
+/// A file's size and modification time when it was scanned, and what was
+/// found and suppressed in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+  size: u64,
+  modified: u128,
+  matches: Vec<CachedMatch>,
+  #[serde(default)]
+  suppressed: FileSuppressions,
+}
+
+/// The parts of a match that come from the file rather than the pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMatch {
+  pub pattern_name: String,
+  pub line_number: u64,
+  pub line: String,
+  pub start: usize,
+  pub end: usize,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub context_before: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub context_after: Vec<String>,
+}
+
+impl ScanCache {
+  /// Read the cache at `path`, starting afresh when it's missing, unreadable
+  /// or was written with different settings than `key` describes
+  pub fn load(path: &Path, key: &str) -> Self {
+    let cache = std::fs::read(path)
+      .ok()
+      .and_then(|data| serde_json::from_slice::<Self>(&data).ok())
+      .filter(|cache| cache.key == key);
+    if cache.is_none() {
+      debug(&format!("Starting a new scan cache at {}", path.display()));
+    }
+    cache.unwrap_or_else(|| Self {
+      key: key.to_string(),
+      files: HashMap::new(),
+    })
+  }
+
+  /// The matches and suppressions recorded for `file`, if it hasn't
+  /// changed since
+  pub fn get(
+    &self,
+    file: &str,
+    metadata: &Metadata,
+  ) -> Option<(&[CachedMatch], &FileSuppressions)> {
+    let cached = self.files.get(file)?;
+    (cached.size == metadata.len()
+      && Some(cached.modified) == modified(metadata))
+    .then_some((cached.matches.as_slice(), &cached.suppressed))
+  }
+
+  /// Record what was found and suppressed in `file`. Files without a usable
+  /// modification time aren't cached.
+  pub fn insert(
+    &mut self,
+    file: String,
+    metadata: &Metadata,
+    matches: &[Match],
+    suppressed: &FileSuppressions,
+  ) {
+    let Some(modified) = modified(metadata) else {
+      return;
+    };
+    let matches = matches
+      .iter()
+      .map(|m| CachedMatch {
+        pattern_name: m.pattern_name.clone(),
+        line_number: m.line_number,
+        line: m.line.clone(),
+        start: m.start,
+        end: m.end,
+        context_before: m.context_before.clone(),
+        context_after: m.context_after.clone(),
+      })
+      .collect();
+    self.files.insert(
+      file,
+      CachedFile {
+        size: metadata.len(),
+        modified,
+        matches,
+        suppressed: suppressed.clone(),
+      },
+    );
+  }
+
+  /// Write the cache to `path`, dropping files that no longer exist
+  pub fn save(mut self, path: &Path) -> Result<()> {
+    self.files.retain(|file, _| Path::new(file).is_file());
+    std::fs::write(path, serde_json::to_vec(&self)?)?;
+    Ok(())
+  }
+}
+
+/// Modification time in nanoseconds since the epoch
+fn modified(metadata: &Metadata) -> Option<u128> {
+  let modified = metadata.modified().ok()?;
+  Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn test_cache_round_trip() -> Result<()> {
+    let temp = TempDir::new()?;
+    let file = temp.path().join("app.env");
+    std::fs::write(&file, "KEY=one\n")?;
+    let label = file.display().to_string();
+    let cache_path = temp.path().join(CACHE_FILE);
+
+    let mut cache = ScanCache::load(&cache_path, "settings");
+    assert!(cache.get(&label, &file.metadata()?).is_none());
+    cache.insert(
+      label.clone(),
+      &file.metadata()?,
+      &[],
+      &FileSuppressions::default(),
+    );
+    cache.save(&cache_path)?;
+
+    let cache = ScanCache::load(&cache_path, "settings");
+    assert_eq!(
+      cache
+        .get(&label, &file.metadata()?)
+        .map(|(matches, _)| matches.len()),
+      Some(0)
+    );
+
+    // A change to the file or the settings misses
+    std::fs::write(&file, "KEY=two!\n")?;
+    assert!(cache.get(&label, &file.metadata()?).is_none());
+    let cache = ScanCache::load(&cache_path, "other settings");
+    assert!(cache.files.is_empty());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,117 @@
+//! JUnit XML output for CI test reporters
+
+use super::Match;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Escape text for use in XML attributes and element content
+fn escape(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&apos;"),
+      c => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// Serialize matches as JUnit XML, with a test suite per pattern and a
+/// failing test case per match. A clean scan is a single passing case, so
+/// reporters still show that the scan ran. The secret itself is left out.
+pub fn to_junit(matches: &[Match]) -> String {
+  let mut suites: BTreeMap<&str, Vec<&Match>> = BTreeMap::new();
+  for m in matches {
+    suites.entry(&m.pattern_name).or_default().push(m);
+  }
+
+  let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+  let total = matches.len().max(1);
+  let _ = writeln!(
+    xml,
+    "<testsuites name=\"ssq\" tests=\"{total}\" failures=\"{}\">",
+    matches.len()
+  );
+
+  if matches.is_empty() {
+    xml.push_str(
+      "  <testsuite name=\"ssq\" tests=\"1\" failures=\"0\">\n    \
+       <testcase name=\"no potential secrets found\" classname=\"ssq\"/>\n  \
+       </testsuite>\n",
+    );
+  }
+
+  for (name, matches) in suites {
+    let name = escape(name);
+    let count = matches.len();
+    let _ = writeln!(
+      xml,
+      "  <testsuite name=\"{name}\" tests=\"{count}\" failures=\"{count}\">"
+    );
+    for m in matches {
+      let location = escape(&format!(
+        "{}:{}:{}",
+        m.file_path, m.line_number, m.column_start
+      ));
+      let message = escape(&format!(
+        "Potential secret: {}",
+        m.pattern.description.as_deref().unwrap_or(&m.pattern_name)
+      ));
+      let severity = m.pattern.severity.as_str();
+      let _ = writeln!(
+        xml,
+        "    <testcase name=\"{location}\" classname=\"{name}\">\n      \
+         <failure message=\"{message}\" type=\"{severity}\">{location} \
+         matched {name}</failure>\n    </testcase>"
+      );
+    }
+    xml.push_str("  </testsuite>\n");
+  }
+
+  xml.push_str("</testsuites>\n");
+  xml
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_match(name: &str, file_path: &str, line_number: u64) -> Match {
+    Match::fixture(name, "Key & <token>", file_path, line_number, "secret")
+  }
+
+  #[test]
+  fn test_junit_output() {
+    let xml = to_junit(&[
+      test_match("github-pat", "a.env", 3),
+      test_match("aws-key", "b\".env", 1),
+      test_match("github-pat", "c.env", 9),
+    ]);
+
+    assert!(
+      xml.contains("<testsuites name=\"ssq\" tests=\"3\" failures=\"3\">")
+    );
+    assert!(
+      xml.find("name=\"aws-key\"").unwrap()
+        < xml.find("name=\"github-pat\"").unwrap()
+    );
+    assert!(xml
+      .contains("<testsuite name=\"github-pat\" tests=\"2\" failures=\"2\">"));
+    assert!(xml.contains("<testcase name=\"b&quot;.env:1:1\""));
+    assert!(
+      xml.contains("message=\"Potential secret: Key &amp; &lt;token&gt;\"")
+    );
+    assert!(!xml.contains(">secret<"));
+  }
+
+  #[test]
+  fn test_junit_clean_scan() {
+    let xml = to_junit(&[]);
+    assert!(xml.contains("tests=\"1\" failures=\"0\""));
+    assert!(!xml.contains("<failure"));
+  }
+}
@@ -0,0 +1,238 @@
+//! SARIF 2.1.0 output for GitHub code scanning and other SARIF consumers
+
+use super::{normalize_path, Match};
+use crate::config::SeverityLevel;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const SARIF_VERSION: &str = "2.1.0";
+
+#[derive(Serialize)]
+struct Log<'a> {
+  #[serde(rename = "$schema")]
+  schema: &'static str,
+  version: &'static str,
+  runs: Vec<Run<'a>>,
+}
+
+#[derive(Serialize)]
+struct Run<'a> {
+  tool: Tool<'a>,
+  results: Vec<SarifResult<'a>>,
+}
+
+#[derive(Serialize)]
+struct Tool<'a> {
+  driver: Driver<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Driver<'a> {
+  name: &'static str,
+  version: &'static str,
+  information_uri: &'static str,
+  rules: Vec<Rule<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Rule<'a> {
+  id: &'a str,
+  short_description: Message,
+  default_configuration: Configuration,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  properties: Option<Properties<'a>>,
+}
+
+#[derive(Serialize)]
+struct Properties<'a> {
+  tags: &'a [String],
+}
+
+#[derive(Serialize)]
+struct Configuration {
+  level: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult<'a> {
+  rule_id: &'a str,
+  rule_index: usize,
+  level: &'static str,
+  message: Message,
+  locations: Vec<Location>,
+}
+
+#[derive(Serialize)]
+struct Message {
+  text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Location {
+  physical_location: PhysicalLocation,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhysicalLocation {
+  artifact_location: ArtifactLocation,
+  region: Region,
+}
+
+#[derive(Serialize)]
+struct ArtifactLocation {
+  uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Region {
+  start_line: u64,
+  start_column: usize,
+  end_column: usize,
+}
+
+/// Maps a pattern severity to a SARIF result level
+fn level(severity: SeverityLevel) -> &'static str {
+  match severity {
+    SeverityLevel::Critical | SeverityLevel::High => "error",
+    SeverityLevel::Medium => "warning",
+    SeverityLevel::Low => "note",
+  }
+}
+
+/// Serialize matches as a SARIF 2.1.0 log with one rule per pattern
+pub fn to_sarif(matches: &[Match]) -> Result<String> {
+  // Each distinct pattern becomes a rule, in a stable order
+  let mut rule_matches: BTreeMap<&str, &Match> = BTreeMap::new();
+  for m in matches {
+    rule_matches.entry(&m.pattern_name).or_insert(m);
+  }
+  let rule_index: BTreeMap<&str, usize> = rule_matches
+    .keys()
+    .enumerate()
+    .map(|(i, name)| (*name, i))
+    .collect();
+
+  let rules = rule_matches
+    .iter()
+    .map(|(name, m)| Rule {
+      id: name,
+      short_description: Message {
+        text: m
+          .pattern
+          .description
+          .clone()
+          .unwrap_or_else(|| (*name).to_string()),
+      },
+      default_configuration: Configuration {
+        level: level(m.pattern.severity),
+      },
+      properties: (!m.pattern.tags.is_empty()).then(|| Properties {
+        tags: &m.pattern.tags,
+      }),
+    })
+    .collect();
+
+  let results = matches
+    .iter()
+    .map(|m| SarifResult {
+      rule_id: &m.pattern_name,
+      rule_index: rule_index[m.pattern_name.as_str()],
+      level: level(m.pattern.severity),
+      message: Message {
+        text: format!(
+          "Potential secret: {}",
+          m.pattern.description.as_deref().unwrap_or(&m.pattern_name)
+        ),
+      },
+      locations: vec![Location {
+        physical_location: PhysicalLocation {
+          artifact_location: ArtifactLocation {
+            // SARIF expects URIs, so separators are normalized
+            uri: normalize_path(&m.file_path),
+          },
+          region: Region {
+            start_line: m.line_number,
+            start_column: m.column_start,
+            end_column: m.column_end,
+          },
+        },
+      }],
+    })
+    .collect();
+
+  let log = Log {
+    schema: SARIF_SCHEMA,
+    version: SARIF_VERSION,
+    runs: vec![Run {
+      tool: Tool {
+        driver: Driver {
+          name: "ssq",
+          version: env!("CARGO_PKG_VERSION"),
+          information_uri: "https://github.com/therealklanni/secret-squirrel",
+          rules,
+        },
+      },
+      results,
+    }],
+  };
+
+  Ok(serde_json::to_string_pretty(&log)?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_match(name: &str, severity: &str, line_number: u64) -> Match {
+    let mut m = Match::fixture(
+      name,
+      &format!("{name} description"),
+      "./src/config.rs",
+      line_number,
+      "secret",
+    );
+    m.pattern.severity = severity.into();
+    m
+  }
+
+  #[test]
+  fn test_sarif_output() -> Result<()> {
+    let matches = vec![
+      test_match("github-pat", "CRITICAL", 3),
+      test_match("generic-password", "LOW", 7),
+      test_match("github-pat", "CRITICAL", 9),
+    ];
+
+    let sarif: serde_json::Value = serde_json::from_str(&to_sarif(&matches)?)?;
+    assert_eq!(sarif["version"], "2.1.0");
+
+    let run = &sarif["runs"][0];
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    assert_eq!(rules.len(), 2);
+    assert_eq!(rules[0]["id"], "generic-password");
+    assert_eq!(rules[1]["id"], "github-pat");
+
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0]["ruleId"], "github-pat");
+    assert_eq!(results[0]["ruleIndex"], 1);
+    assert_eq!(results[0]["level"], "error");
+    assert_eq!(results[1]["level"], "note");
+
+    let location = &results[2]["locations"][0]["physicalLocation"];
+    assert_eq!(location["artifactLocation"]["uri"], "src/config.rs");
+    assert_eq!(location["region"]["startLine"], 9);
+    assert_eq!(location["region"]["startColumn"], 1);
+    assert_eq!(location["region"]["endColumn"], 7);
+
+    Ok(())
+  }
+}
@@ -0,0 +1,120 @@
+use super::Match;
+use grep_searcher::{Searcher, Sink, SinkContext, SinkMatch};
+use std::collections::VecDeque;
+
+/// Collects matches from a search, attaching up to `context` lines from
+/// before and after each matched line. `on_match` decides whether a matched
+/// line is recorded. The search stops once `matches` holds `limit` entries
+/// and their after-context is complete.
+pub struct ContextSink<'m, F> {
+  context: usize,
+  limit: usize,
+  matches: &'m mut Vec<Match>,
+  on_match: F,
+  /// The last `context` lines seen, with their line numbers
+  recent: VecDeque<(u64, String)>,
+  /// Recorded matches still collecting after-context: (index, line number)
+  pending: Vec<(usize, u64)>,
+}
+
+impl<'m, F> ContextSink<'m, F>
+where
+  F: FnMut(u64, &str) -> Option<Match>,
+{
+  pub fn new(
+    context: usize,
+    limit: usize,
+    matches: &'m mut Vec<Match>,
+    on_match: F,
+  ) -> Self {
+    Self {
+      context,
+      limit,
+      matches,
+      on_match,
+      recent: VecDeque::with_capacity(context),
+      pending: Vec::new(),
+    }
+  }
+
+  /// Feed a line to every match still waiting on after-context, and
+  /// remember it as potential before-context for later matches
+  fn see_line(&mut self, line_number: u64, line: &str) {
+    if self.context == 0 {
+      return;
+    }
+
+    let context = self.context as u64;
+    for &(i, match_line) in &self.pending {
+      if line_number > match_line && line_number <= match_line + context {
+        self.matches[i].context_after.push(line.to_string());
+      }
+    }
+    self
+      .pending
+      .retain(|&(_, match_line)| line_number < match_line + context);
+
+    if self.recent.len() == self.context {
+      self.recent.pop_front();
+    }
+    self.recent.push_back((line_number, line.to_string()));
+  }
+
+  fn is_full(&self) -> bool {
+    self.matches.len() >= self.limit && self.pending.is_empty()
+  }
+}
+
+fn line_text(bytes: &[u8]) -> String {
+  String::from_utf8_lossy(bytes)
+    .trim_end_matches(['\r', '\n'])
+    .to_string()
+}
+
+impl<F> Sink for ContextSink<'_, F>
+where
+  F: FnMut(u64, &str) -> Option<Match>,
+{
+  type Error = std::io::Error;
+
+  fn matched(
+    &mut self,
+    _searcher: &Searcher,
+    mat: &SinkMatch<'_>,
+  ) -> Result<bool, Self::Error> {
+    let line_number = mat.line_number().unwrap_or_default();
+    let line = String::from_utf8_lossy(mat.bytes());
+
+    let recorded = if self.matches.len() < self.limit {
+      (self.on_match)(line_number, &line)
+    } else {
+      None
+    };
+    if let Some(mut m) = recorded {
+      let context = self.context as u64;
+      m.context_before = self
+        .recent
+        .iter()
+        .filter(|(n, _)| n + context >= line_number)
+        .map(|(_, text)| text.clone())
+        .collect();
+      if self.context > 0 {
+        self.pending.push((self.matches.len(), line_number));
+      }
+      self.matches.push(m);
+    }
+
+    self.see_line(line_number, &line_text(mat.bytes()));
+    Ok(!self.is_full())
+  }
+
+  fn context(
+    &mut self,
+    _searcher: &Searcher,
+    ctx: &SinkContext<'_>,
+  ) -> Result<bool, Self::Error> {
+    let line_number = ctx.line_number().unwrap_or_default();
+    self.see_line(line_number, &line_text(ctx.bytes()));
+    Ok(!self.is_full())
+  }
+}
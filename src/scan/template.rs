@@ -0,0 +1,149 @@
+//! One line per match in a user-supplied format, for `--template`
+
+use super::Match;
+use std::str::FromStr;
+
+/// Placeholders a template may use, as written between braces
+const FIELDS: &[(&str, Field)] = &[
+  ("file", Field::File),
+  ("line", Field::Line),
+  ("col", Field::Column),
+  ("pattern", Field::Pattern),
+  ("severity", Field::Severity),
+  ("match", Field::Match),
+  ("desc", Field::Description),
+];
+
+/// A `--template` such as `{file}:{line}:{pattern}`, parsed once and
+/// rendered for each match. `\t` and `\n` in the template stand for a tab
+/// and a newline, so they can be passed without shell quoting tricks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchTemplate {
+  parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+  Text(String),
+  Field(Field),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+  File,
+  Line,
+  Column,
+  Pattern,
+  Severity,
+  Match,
+  Description,
+}
+
+impl FromStr for MatchTemplate {
+  type Err = String;
+
+  fn from_str(template: &str) -> Result<Self, Self::Err> {
+    let template = template.replace("\\t", "\t").replace("\\n", "\n");
+    let mut parts = Vec::new();
+    let mut rest = template.as_str();
+    while let Some(open) = rest.find('{') {
+      let close = rest[open..]
+        .find('}')
+        .map(|close| open + close)
+        .ok_or_else(|| format!("unclosed '{{' in template: {template}"))?;
+      let name = &rest[open + 1..close];
+      let (_, field) = FIELDS
+        .iter()
+        .find(|(field, _)| *field == name)
+        .ok_or_else(|| {
+          format!(
+            "unknown placeholder {{{name}}}, expected one of {}",
+            FIELDS
+              .iter()
+              .map(|(field, _)| format!("{{{field}}}"))
+              .collect::<Vec<_>>()
+              .join(", ")
+          )
+        })?;
+      if open > 0 {
+        parts.push(Part::Text(rest[..open].to_string()));
+      }
+      parts.push(Part::Field(*field));
+      rest = &rest[close + 1..];
+    }
+    if !rest.is_empty() {
+      parts.push(Part::Text(rest.to_string()));
+    }
+    Ok(Self { parts })
+  }
+}
+
+impl MatchTemplate {
+  /// Fill in the template for `m`. `secret` is the matched text as it
+  /// should be shown, e.g. redacted.
+  pub fn render(&self, m: &Match, secret: &str) -> String {
+    let mut line = String::new();
+    for part in &self.parts {
+      match part {
+        Part::Text(text) => line.push_str(text),
+        Part::Field(Field::File) => line.push_str(&m.file_path),
+        Part::Field(Field::Line) => line.push_str(&m.line_number.to_string()),
+        Part::Field(Field::Column) => {
+          line.push_str(&m.column_start.to_string());
+        }
+        Part::Field(Field::Pattern) => line.push_str(&m.pattern_name),
+        Part::Field(Field::Severity) => {
+          line.push_str(m.pattern.severity.as_str());
+        }
+        Part::Field(Field::Match) => line.push_str(secret),
+        Part::Field(Field::Description) => {
+          line.push_str(m.pattern.description.as_deref().unwrap_or_default());
+        }
+      }
+    }
+    line
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Pattern;
+
+  #[test]
+  fn test_render_template() -> Result<(), String> {
+    let pattern = Pattern {
+      description: Some("API key".into()),
+      regex: String::new(),
+      severity: "HIGH".into(),
+      ..Pattern::default()
+    };
+    let m = Match::new(
+      "api-key",
+      &pattern,
+      "src/app.env".into(),
+      7,
+      "KEY=abc123",
+      (4, 10),
+    );
+
+    let template: MatchTemplate =
+      "{severity}\\t{pattern}\\t{file}:{line}".parse()?;
+    assert_eq!(
+      template.render(&m, "abc123"),
+      "HIGH\tapi-key\tsrc/app.env:7"
+    );
+    let template: MatchTemplate =
+      "{file}:{line}:{col} {desc}={match}!".parse()?;
+    assert_eq!(
+      template.render(&m, "ab**23"),
+      "src/app.env:7:5 API key=ab**23!"
+    );
+
+    assert!("{file".parse::<MatchTemplate>().is_err());
+    let err = "{path}".parse::<MatchTemplate>().unwrap_err();
+    assert!(err.contains("{path}") && err.contains("{file}"), "{err}");
+
+    Ok(())
+  }
+}
@@ -0,0 +1,451 @@
+//! Going through matches one at a time after a scan with `--interactive`,
+//! then writing the suppressions chosen for them
+
+use crate::baseline::Baseline;
+use crate::config::Config;
+use crate::scan::{Match, Scanner, INLINE_IGNORE};
+use anyhow::{Context, Result};
+use console::{style, Key, Term};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// What to do about a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+  /// Leave it reported
+  Keep,
+  /// Add an `ssq:ignore` comment above its line
+  IgnoreInline,
+  /// Add the matched value to `allowed_values` in the local config
+  AllowValue,
+  /// Record it in the `--baseline` file
+  Baseline,
+}
+
+/// Keys offered at the prompt, and what each decides
+const CHOICES: &[(char, &str, Decision)] = &[
+  ('k', "[k]eep", Decision::Keep),
+  ('i', "[i]gnore inline", Decision::IgnoreInline),
+  ('a', "[a]llow value", Decision::AllowValue),
+  ('b', "[b]aseline", Decision::Baseline),
+];
+
+/// Comment delimiters by file extension, for the `ssq:ignore` line added
+/// above a match. Files without one can't be ignored inline.
+const COMMENT_SYNTAX: &[(&[&str], &str, &str)] = &[
+  (
+    &[
+      "env",
+      "sh",
+      "bash",
+      "zsh",
+      "py",
+      "rb",
+      "pl",
+      "yml",
+      "yaml",
+      "toml",
+      "ini",
+      "cfg",
+      "conf",
+      "properties",
+      "tf",
+      "hcl",
+      "r",
+      "ps1",
+      "dockerfile",
+      "makefile",
+    ],
+    "#",
+    "",
+  ),
+  (
+    &[
+      "rs", "go", "js", "jsx", "mjs", "cjs", "ts", "tsx", "java", "kt", "kts",
+      "c", "h", "cc", "cpp", "hpp", "cs", "swift", "scala", "php", "dart",
+      "groovy", "gradle", "jsonc",
+    ],
+    "//",
+    "",
+  ),
+  (&["sql", "lua", "hs"], "--", ""),
+  (&["html", "xml", "md", "vue", "svg"], "<!--", " -->"),
+  (&["css"], "/*", " */"),
+];
+
+/// How to write a comment on its own line in the file at `path`
+fn comment_syntax(path: &Path) -> Option<(&'static str, &'static str)> {
+  let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+  let kind = if name == ".env" || name.starts_with(".env.") {
+    "env"
+  } else {
+    name.rsplit_once('.').map_or(name.as_str(), |(_, ext)| ext)
+  };
+  COMMENT_SYNTAX
+    .iter()
+    .find(|(kinds, ..)| kinds.contains(&kind))
+    .map(|&(_, open, close)| (open, close))
+}
+
+/// The file an `ssq:ignore` comment for `m` would go in, if it can have one
+fn inline_target(scanner: &Scanner, m: &Match) -> Option<PathBuf> {
+  scanner
+    .source_path(m)
+    .filter(|path| comment_syntax(path).is_some())
+}
+
+/// Show each of the scanner's matches and ask what to do about it. Quitting
+/// keeps the rest; Ctrl-C abandons triage without changing anything.
+pub fn prompt(
+  scanner: &Scanner,
+  term: &Term,
+  baseline: bool,
+) -> Result<Vec<Decision>> {
+  let matches = scanner.matches();
+  let mut decisions = vec![Decision::Keep; matches.len()];
+  for (i, m) in matches.iter().enumerate() {
+    let mut shown = Vec::new();
+    scanner.write_match(&mut shown, m)?;
+    term.write_str(&String::from_utf8_lossy(&shown))?;
+
+    let choices: Vec<_> = CHOICES
+      .iter()
+      .filter(|(_, _, decision)| match decision {
+        Decision::IgnoreInline => inline_target(scanner, m).is_some(),
+        Decision::Baseline => baseline,
+        _ => true,
+      })
+      .collect();
+    let labels: Vec<_> = choices.iter().map(|(_, label, _)| *label).collect();
+    term.write_str(&format!(
+      "\n{} {}, [q]uit? ",
+      style(format!("({}/{})", i + 1, matches.len())).dim(),
+      labels.join(", ")
+    ))?;
+
+    decisions[i] = loop {
+      match term.read_key() {
+        Ok(Key::Char('q') | Key::Escape) => {
+          term.write_line("q")?;
+          return Ok(decisions);
+        }
+        Ok(Key::Char(c)) => {
+          let c = c.to_ascii_lowercase();
+          if let Some((key, _, decision)) =
+            choices.iter().find(|(key, ..)| *key == c)
+          {
+            term.write_line(&key.to_string())?;
+            break *decision;
+          }
+        }
+        Ok(_) => {}
+        Err(err) if err.kind() == ErrorKind::Interrupted => {
+          term.write_line("")?;
+          anyhow::bail!("Triage cancelled, nothing was changed");
+        }
+        Err(err) => return Err(err.into()),
+      }
+    };
+  }
+  Ok(decisions)
+}
+
+/// Write the suppression chosen for each of the scanner's matches:
+/// `ssq:ignore` comments into its files, values into the local config's
+/// `allowed_values` and findings into `baseline`. Matches whose line has
+/// changed since the scan aren't ignored and go back to `Keep`. What was
+/// written is reported to `out`.
+pub fn apply(
+  scanner: &Scanner,
+  decisions: &mut [Decision],
+  baseline: Option<&Path>,
+  out: &mut impl Write,
+) -> Result<()> {
+  let matches = scanner.matches();
+  let chosen = |decision: Decision| {
+    matches
+      .iter()
+      .zip(decisions.iter())
+      .filter(move |(_, chosen)| **chosen == decision)
+      .map(|(m, _)| m)
+  };
+
+  let mut values: Vec<&str> = Vec::new();
+  for m in chosen(Decision::AllowValue) {
+    if !values.contains(&m.secret.as_str()) {
+      values.push(&m.secret);
+    }
+  }
+  if !values.is_empty() {
+    let path = Config::local_config_path()?;
+    let contents = match fs::read_to_string(&path) {
+      Ok(contents) => contents,
+      Err(err) if err.kind() == ErrorKind::NotFound => String::new(),
+      Err(err) => {
+        return Err(err)
+          .with_context(|| format!("Failed to read {}", path.display()))
+      }
+    };
+    fs::write(&path, add_allowed_values(&contents, &values)?)
+      .with_context(|| format!("Failed to write {}", path.display()))?;
+    writeln!(
+      out,
+      "Added {} values to allowed_values in {}",
+      values.len(),
+      path.display()
+    )?;
+  }
+
+  let known: Vec<Match> = chosen(Decision::Baseline).cloned().collect();
+  if let Some(path) = baseline.filter(|_| !known.is_empty()) {
+    let mut baseline = if path.exists() {
+      Baseline::load(path)?
+    } else {
+      Baseline::from_matches(&[])
+    };
+    baseline.extend(&known);
+    baseline.save(path)?;
+    writeln!(
+      out,
+      "Added {} findings to baseline {}",
+      known.len(),
+      path.display()
+    )?;
+  }
+
+  let mut files: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+  for (i, m) in matches.iter().enumerate() {
+    if decisions[i] == Decision::IgnoreInline {
+      match inline_target(scanner, m) {
+        Some(path) => files.entry(path).or_default().push(i),
+        None => decisions[i] = Decision::Keep,
+      }
+    }
+  }
+  for (path, indexes) in files {
+    let Some(syntax) = comment_syntax(&path) else {
+      continue;
+    };
+    let contents = fs::read_to_string(&path)
+      .with_context(|| format!("Failed to read {}", path.display()))?;
+    let ignored: Vec<&Match> = indexes.iter().map(|&i| &matches[i]).collect();
+    let label = &ignored[0].file_path;
+    let (edited, changed) = add_ignore_comments(&contents, syntax, &ignored);
+    for &i in &indexes {
+      if changed.contains(&matches[i].line_number) {
+        decisions[i] = Decision::Keep;
+        writeln!(
+          out,
+          "{label}:{} has changed since the scan, so it wasn't ignored",
+          matches[i].line_number
+        )?;
+      }
+    }
+    if edited != contents {
+      fs::write(&path, edited)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+      writeln!(
+        out,
+        "Ignored {} matches in {label}",
+        indexes.len() - changed.len()
+      )?;
+    }
+  }
+
+  Ok(())
+}
+
+/// `contents` with an `ssq:ignore=<pattern>` comment above the line of each
+/// of `matches`, indented like it, and the line numbers that no longer hold
+/// the matched line. A directive already above a line is extended instead.
+fn add_ignore_comments(
+  contents: &str,
+  (open, close): (&str, &str),
+  matches: &[&Match],
+) -> (String, Vec<u64>) {
+  let lines: Vec<&str> = contents.split_inclusive('\n').collect();
+  let mut names: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+  let mut changed = Vec::new();
+  for m in matches {
+    let index = usize::try_from(m.line_number)
+      .ok()
+      .and_then(|line_number| line_number.checked_sub(1));
+    let current = index.and_then(|index| lines.get(index));
+    match index.filter(|_| {
+      current.is_some_and(|line| line.trim_end_matches(['\r', '\n']) == m.line)
+    }) {
+      Some(index) => {
+        let names = names.entry(index).or_default();
+        if !names.contains(&m.pattern_name.as_str()) {
+          names.push(&m.pattern_name);
+        }
+      }
+      None => changed.push(m.line_number),
+    }
+  }
+
+  let directive = format!("{INLINE_IGNORE}=");
+  let mut edited: Vec<String> = Vec::with_capacity(lines.len());
+  for (index, line) in lines.iter().enumerate() {
+    if let Some(names) = names.get(&index) {
+      let names = names.join(",");
+      // A directive on its own on the line above only covers this line, so
+      // another would push it out of reach
+      let above = edited.last_mut().and_then(|above| {
+        let start = above.find(&directive)?;
+        (!above[..start].chars().any(char::is_alphanumeric))
+          .then_some((above, start + directive.len()))
+      });
+      match above {
+        Some((above, start)) => {
+          let end = above[start..]
+            .find(char::is_whitespace)
+            .map_or(above.len(), |end| start + end);
+          above.insert_str(end, &format!(",{names}"));
+        }
+        None => {
+          let indent = &line[..line.len() - line.trim_start().len()];
+          let indent = indent.trim_end_matches(['\r', '\n']);
+          let newline = if line.ends_with("\r\n") { "\r\n" } else { "\n" };
+          edited
+            .push(format!("{indent}{open} {directive}{names}{close}{newline}"));
+        }
+      }
+    }
+    edited.push(line.to_string());
+  }
+  (edited.concat(), changed)
+}
+
+/// `contents` with `values` added to its `allowed_values` list. The list is
+/// edited in place when that's simple, keeping the file's comments, and the
+/// whole config is rewritten when it isn't.
+fn add_allowed_values(contents: &str, values: &[&str]) -> Result<String> {
+  let items = values
+    .iter()
+    .map(|value| Ok(serde_yaml::to_string(value)?.trim_end().to_string()))
+    .collect::<Result<Vec<_>>>()?;
+  let lines: Vec<&str> = contents.lines().collect();
+  let mut edited: Vec<String> = Vec::new();
+  match lines
+    .iter()
+    .position(|line| line.trim_end() == "allowed_values:")
+  {
+    Some(key) => {
+      // Match the indentation of the existing entries
+      let indent = lines
+        .get(key + 1)
+        .filter(|line| line.trim_start().starts_with("- "))
+        .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+      edited.extend(lines[..=key].iter().map(ToString::to_string));
+      edited.extend(items.iter().map(|item| format!("{indent}- {item}")));
+      edited.extend(lines[key + 1..].iter().map(ToString::to_string));
+    }
+    None => {
+      edited.extend(lines.iter().map(ToString::to_string));
+      edited.push("allowed_values:".to_string());
+      edited.extend(items.iter().map(|item| format!("- {item}")));
+    }
+  }
+  let edited = edited.join("\n") + "\n";
+  if lists_values(&edited, values) {
+    return Ok(edited);
+  }
+
+  let mut config: Value = serde_yaml::from_str(contents)?;
+  if config.is_null() {
+    config = Value::Mapping(serde_yaml::Mapping::new());
+  }
+  let list = config
+    .as_mapping_mut()
+    .context("The config isn't a mapping")?
+    .entry("allowed_values".into())
+    .or_insert_with(|| Value::Sequence(Vec::new()));
+  list
+    .as_sequence_mut()
+    .context("allowed_values in the config isn't a list")?
+    .extend(values.iter().map(|&value| Value::from(value)));
+  Ok(serde_yaml::to_string(&config)?)
+}
+
+/// Whether `contents` parses as a config listing all of `values` as allowed
+fn lists_values(contents: &str, values: &[&str]) -> bool {
+  serde_yaml::from_str::<Config>(contents).is_ok_and(|config| {
+    values
+      .iter()
+      .all(|value| config.allowed_values.iter().any(|v| v == value))
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn test_match(name: &str, line_number: u64, line: &str) -> Match {
+    Match::fixture(name, "Test key", "app.py", line_number, line)
+  }
+
+  #[test]
+  fn test_add_ignore_comments() {
+    let contents = "def f():\n    key = 'abc'\n    other = 'def'\r\n";
+    let (edited, changed) = add_ignore_comments(
+      contents,
+      ("#", ""),
+      &[
+        &test_match("api-key", 2, "    key = 'abc'"),
+        &test_match("token", 2, "    key = 'abc'"),
+        &test_match("token", 3, "    other = 'def'"),
+        &test_match("token", 4, "gone"),
+      ],
+    );
+    assert_eq!(
+      edited,
+      "def f():\n    # ssq:ignore=api-key,token\n    key = 'abc'\n    \
+       # ssq:ignore=token\r\n    other = 'def'\r\n"
+    );
+    assert_eq!(changed, [4]);
+
+    // An existing directive above the line is extended
+    let (edited, _) = add_ignore_comments(
+      "<!-- ssq:ignore=aws-key -->\nkey\n",
+      ("<!--", " -->"),
+      &[&test_match("token", 2, "key")],
+    );
+    assert_eq!(edited, "<!-- ssq:ignore=aws-key,token -->\nkey\n");
+
+    assert_eq!(comment_syntax(Path::new(".env.local")), Some(("#", "")));
+    assert_eq!(comment_syntax(Path::new("src/App.TSX")), Some(("//", "")));
+    assert_eq!(comment_syntax(Path::new("data.json")), None);
+  }
+
+  #[test]
+  fn test_add_allowed_values() -> Result<()> {
+    // Added to an existing list in place, keeping comments
+    let contents = "# Project config\nallowed_values:\n  - EXAMPLE\n\
+                    severity: LOW\n";
+    assert_eq!(
+      add_allowed_values(contents, &["abc", "123"])?,
+      "# Project config\nallowed_values:\n  - abc\n  - '123'\n  - EXAMPLE\n\
+       severity: LOW\n"
+    );
+
+    // Or as a new list
+    assert_eq!(
+      add_allowed_values("severity: LOW", &["abc"])?,
+      "severity: LOW\nallowed_values:\n- abc\n"
+    );
+    assert_eq!(
+      add_allowed_values("", &["abc"])?,
+      "allowed_values:\n- abc\n"
+    );
+
+    // A list in flow style is rewritten
+    let edited = add_allowed_values("allowed_values: [EXAMPLE]\n", &["abc"])?;
+    assert!(lists_values(&edited, &["EXAMPLE", "abc"]), "{edited}");
+
+    Ok(())
+  }
+}
@@ -1,9 +1,21 @@
-use std::io::{stdout, Write};
+use std::{
+  collections::VecDeque,
+  io::{stdout, Write},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
+  thread::JoinHandle,
+  time::{Duration, Instant},
+};
 
 use anyhow::Result;
+use parking_lot::Mutex;
 use ratatui::{
+  backend::{Backend, CrosstermBackend},
   crossterm::{
     cursor::{Hide, Show},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{
       disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
@@ -14,68 +26,171 @@ use ratatui::{
   style::{Color, Style},
   text::{Line, Span},
   widgets::{Block, Borders, Paragraph},
-  Frame, Terminal,
+  Frame, Terminal, TerminalOptions, Viewport,
 };
 
+use crate::progress::ProgressReporter;
+
 const MIN_PATH_WIDTH: usize = 20;
 const PROGRESS_WIDTH: usize = 12; // [███░░░░░] 99/99
 const SPINNER_WIDTH: usize = 2; // "⟳ "
 const SPACING: usize = 2; // spaces between columns
+const PLAIN_PROGRESS_STEPS: usize = 10;
+const FRAME_INTERVAL: Duration = Duration::from_millis(66); // ~15fps
+const INLINE_HEIGHT: u16 = 12; // rows drawn below the cursor with --inline
+const RATE_WINDOW: Duration = Duration::from_secs(10); // for the ETA's files/sec
+const MIN_RATE_SPAN: Duration = Duration::from_secs(1); // shorter shows "--"
 
+/// Set while the terminal may be in raw mode or on the alternate screen, so
+/// cleanup doesn't write escape sequences to logs that never saw the TUI
+static TUI_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Set while on the alternate screen. Leaving it when we never entered can
+/// restore a stale cursor position in some terminals.
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+type Term = Terminal<CrosstermBackend<std::io::Stdout>>;
+
+/// Scan progress display. Interactive terminals get a full-screen TUI;
+/// otherwise (e.g. in CI) progress is printed as plain lines on stderr.
+///
+/// Nothing is drawn until the scan starts, and the terminal is restored
+/// when it finishes.
 pub struct ScanUI {
-  terminal: Terminal<ratatui::backend::CrosstermBackend<std::io::Stdout>>,
+  state: Arc<Mutex<State>>,
+  plain: bool,
+  inline: bool,
+  running: Arc<AtomicBool>,
+  done: Arc<AtomicBool>,
+  render_thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+#[derive(Default)]
+struct State {
+  interactive: bool,
   total_files: usize,
   processed_files: usize,
-  problem_files: Vec<String>,
+  /// Files with matches, in the order found, with how many each has
+  problem_files: Vec<(String, usize)>,
   active_scans: Vec<(String, String, f32)>, // (path, message, progress)
+  started: Option<Instant>,
+  /// When files finished within the last `RATE_WINDOW`, oldest first
+  recent_completions: VecDeque<Instant>,
 }
 
 impl ScanUI {
   pub fn cleanup() {
+    if !TUI_ACTIVE.swap(false, Ordering::SeqCst) {
+      return;
+    }
     let mut stdout = stdout();
     let _ = disable_raw_mode();
-    let _ = execute!(stdout, LeaveAlternateScreen, Show);
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+      let _ = execute!(stdout, LeaveAlternateScreen);
+    }
+    let _ = execute!(stdout, Show);
     let _ = stdout.flush();
   }
 
-  pub fn new(total_files: usize) -> Result<Self> {
-    let backend = ratatui::backend::CrosstermBackend::new(stdout());
-    let terminal = Terminal::new(backend)?;
+  /// Create a progress display, falling back to plain output when `plain`
+  /// is set or stdout isn't a terminal. Clearing `running` cancels the
+  /// scan, as Ctrl-C does.
+  pub fn new(plain: bool, running: Arc<AtomicBool>) -> Self {
+    Self {
+      state: Arc::new(Mutex::new(State::default())),
+      plain,
+      inline: false,
+      running,
+      done: Arc::new(AtomicBool::new(false)),
+      render_thread: Mutex::new(None),
+    }
+  }
 
-    Ok(Self {
-      terminal,
-      total_files,
-      processed_files: 0,
-      problem_files: Vec::new(),
-      active_scans: Vec::new(),
-    })
+  /// Draw below the cursor instead of on the alternate screen, so the final
+  /// frame stays in the terminal's scrollback
+  pub fn set_inline(&mut self, inline: bool) {
+    self.inline = inline;
   }
 
-  pub fn render(&mut self) -> Result<()> {
+  fn enter_tui(inline: bool) -> Result<Term> {
+    TUI_ACTIVE.store(true, Ordering::SeqCst);
     enable_raw_mode()?;
+    let backend = CrosstermBackend::new(stdout());
+    if inline {
+      execute!(stdout(), Hide)?;
+      let viewport = Viewport::Inline(INLINE_HEIGHT);
+      return Ok(Terminal::with_options(
+        backend,
+        TerminalOptions { viewport },
+      )?);
+    }
+    ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
     execute!(stdout(), EnterAlternateScreen, Hide)?;
+    Ok(Terminal::new(backend)?)
+  }
+
+  /// Redraw at a fixed rate until `done` is set.
+  ///
+  /// Raw mode delivers Ctrl-C as a key press instead of SIGINT, so it's
+  /// handled here the same way as the signal handler in `main`.
+  fn render_loop<B: Backend>(
+    terminal: &mut Terminal<B>,
+    state: &Mutex<State>,
+    done: &AtomicBool,
+    running: &AtomicBool,
+  ) {
+    while !done.load(Ordering::SeqCst) {
+      let _ = Self::render(terminal, &state.lock());
+      if Self::poll_ctrl_c() && !running.swap(false, Ordering::SeqCst) {
+        Self::cleanup();
+        std::process::exit(130);
+      }
+    }
+    let _ = Self::render(terminal, &state.lock());
 
-    self.terminal.draw(|f| {
+    // Inline, carry on below the last frame rather than drawing over it
+    if !ALTERNATE_SCREEN.load(Ordering::SeqCst) {
+      let bottom = terminal.get_frame().area().bottom();
+      let _ = terminal.set_cursor_position((0, bottom.saturating_sub(1)));
+      println!();
+    }
+  }
+
+  /// Wait up to one frame for input, returning whether it was Ctrl-C
+  fn poll_ctrl_c() -> bool {
+    if !event::poll(FRAME_INTERVAL).unwrap_or(false) {
+      return false;
+    }
+    matches!(
+      event::read(),
+      Ok(Event::Key(key))
+        if key.kind == KeyEventKind::Press
+          && key.code == KeyCode::Char('c')
+          && key.modifiers.contains(KeyModifiers::CONTROL)
+    )
+  }
+
+  /// Draw the current state. Callers treat errors as best-effort since a
+  /// lost terminal shouldn't abort the scan.
+  fn render<B: Backend>(
+    terminal: &mut Terminal<B>,
+    state: &State,
+  ) -> Result<()> {
+    terminal.draw(|f| {
       Self::draw_frame(
         f,
-        self.total_files,
-        self.processed_files,
-        &self.problem_files,
-        &self.active_scans,
+        &state.progress_title(Instant::now()),
+        &state.problem_files,
+        &state.active_scans,
       );
     })?;
 
-    disable_raw_mode()?;
-    execute!(stdout(), Show)?;
-
     Ok(())
   }
 
   fn draw_frame(
     f: &mut Frame,
-    total_files: usize,
-    processed_files: usize,
-    problem_files: &[String],
+    progress: &str,
+    problem_files: &[(String, usize)],
     active_scans: &[(String, String, f32)],
   ) {
     let area = f.area();
@@ -108,11 +223,18 @@ impl ScanUI {
     if has_problems {
       let problems: Vec<Line> = problem_files
         .iter()
-        .map(|path| {
-          Line::from(vec![
+        .map(|(path, count)| {
+          let mut spans = vec![
             Span::styled("● ", Style::default().fg(Color::Red)),
             Span::raw(path),
-          ])
+          ];
+          if *count > 1 {
+            spans.push(Span::styled(
+              format!(" ({count})"),
+              Style::default().fg(Color::DarkGray),
+            ));
+          }
+          Line::from(spans)
         })
         .collect();
 
@@ -178,45 +300,159 @@ impl ScanUI {
           .borders(Borders::ALL)
           .border_style(Style::default())
           .title(Line::from("Active Scans").left_aligned())
-          .title(
-            Line::from(format!("Progress: {processed_files}/{total_files}"))
-              .right_aligned(),
-          ),
+          .title(Line::from(progress).right_aligned()),
       ),
       if has_problems { chunks[1] } else { chunks[0] },
     );
   }
+}
+
+impl State {
+  /// Files done out of the total, time since the scan started and, once
+  /// there's enough to go on, an estimate of the time left from the recent
+  /// rate of files finishing
+  fn progress_title(&self, now: Instant) -> String {
+    let started = self.started.unwrap_or(now);
+    let elapsed = now.duration_since(started);
+    let remaining = self.total_files.saturating_sub(self.processed_files);
+    let window = elapsed.min(RATE_WINDOW);
+    let recent = self
+      .recent_completions
+      .iter()
+      .filter(|&&done| now.duration_since(done) <= window)
+      .count();
+    let eta = if remaining == 0 {
+      format_duration(Duration::ZERO)
+    } else if window < MIN_RATE_SPAN || recent == 0 {
+      "--".to_string()
+    } else {
+      #[allow(clippy::cast_precision_loss)]
+      let seconds_left =
+        window.as_secs_f64() / recent as f64 * remaining as f64;
+      format_duration(Duration::from_secs_f64(seconds_left))
+    };
+    format!(
+      "Progress: {}/{} · {} elapsed · ETA {eta}",
+      self.processed_files,
+      self.total_files,
+      format_duration(elapsed)
+    )
+  }
 
-  pub fn add_problem_file(&mut self, path: String) {
-    self.problem_files.push(path);
-    self.render().unwrap();
+  /// A progress line roughly every tenth of the way through the scan
+  fn plain_progress(&self) -> Option<String> {
+    let step = (self.total_files / PLAIN_PROGRESS_STEPS).max(1);
+    (self.processed_files.is_multiple_of(step)
+      || self.processed_files == self.total_files)
+      .then(|| {
+        format!(
+          "Scanned {}/{} files",
+          self.processed_files, self.total_files
+        )
+      })
   }
+}
 
-  pub fn update_scan(&mut self, path: String, message: String, progress: f32) {
-    if let Some(scan) = self.active_scans.iter_mut().find(|(p, ..)| p == &path)
+impl ProgressReporter for ScanUI {
+  fn start(&self, total_files: usize) {
+    let mut state = self.state.lock();
+    state.total_files = total_files;
+    state.started = Some(Instant::now());
+    if self.plain || !console::Term::stdout().is_term() {
+      return;
+    }
+
+    match Self::enter_tui(self.inline) {
+      Ok(mut terminal) => {
+        state.interactive = true;
+        let state = self.state.clone();
+        let done = self.done.clone();
+        let running = self.running.clone();
+        *self.render_thread.lock() = Some(std::thread::spawn(move || {
+          Self::render_loop(&mut terminal, &state, &done, &running);
+        }));
+      }
+      // Fall back to plain progress rather than failing the scan
+      Err(_) => Self::cleanup(),
+    }
+  }
+
+  fn update(&self, path: &str, msg: &str, pct: f32) {
+    let mut state = self.state.lock();
+    if let Some(scan) = state.active_scans.iter_mut().find(|(p, ..)| p == path)
     {
-      *scan = (path, message, progress);
+      *scan = (path.to_string(), msg.to_string(), pct);
     } else {
-      self.active_scans.push((path, message, progress));
+      state
+        .active_scans
+        .push((path.to_string(), msg.to_string(), pct));
     }
-    self.render().unwrap();
   }
 
-  pub fn complete_scan(&mut self, path: &str) {
-    self.processed_files += 1;
-    self.active_scans.retain(|(p, ..)| p != path);
-    self.render().unwrap();
+  fn problem(&self, path: &str) {
+    let mut state = self.state.lock();
+    if let Some((_, count)) =
+      state.problem_files.iter_mut().find(|(p, _)| p == path)
+    {
+      *count += 1;
+      return;
+    }
+    if !state.interactive {
+      eprintln!("Potential secrets in {path}");
+    }
+    state.problem_files.push((path.to_string(), 1));
+  }
+
+  fn complete(&self, path: &str) {
+    let mut state = self.state.lock();
+    state.processed_files += 1;
+    state.active_scans.retain(|(p, ..)| p != path);
+    let now = Instant::now();
+    state.recent_completions.push_back(now);
+    while state
+      .recent_completions
+      .front()
+      .is_some_and(|&done| now.duration_since(done) > RATE_WINDOW)
+    {
+      state.recent_completions.pop_front();
+    }
+    if !state.interactive {
+      if let Some(line) = state.plain_progress() {
+        eprintln!("{line}");
+      }
+    }
+  }
+
+  /// Stop drawing and restore the terminal
+  fn finish(&self) {
+    self.done.store(true, Ordering::SeqCst);
+    if let Some(thread) = self.render_thread.lock().take() {
+      let _ = thread.join();
+    }
+    Self::cleanup();
   }
 }
 
 impl Drop for ScanUI {
   fn drop(&mut self) {
-    Self::cleanup();
+    self.finish();
+  }
+}
+
+/// `m:ss`, or `h:mm:ss` from an hour on
+fn format_duration(duration: Duration) -> String {
+  let seconds = duration.as_secs();
+  let (hours, minutes, seconds) =
+    (seconds / 3600, seconds / 60 % 60, seconds % 60);
+  if hours > 0 {
+    format!("{hours}:{minutes:02}:{seconds:02}")
+  } else {
+    format!("{minutes}:{seconds:02}")
   }
 }
 
 fn truncate_path(path: &str, max_len: usize) -> String {
-  if path.len() <= max_len {
+  if path.chars().count() <= max_len {
     path.to_string()
   } else {
     let parts: Vec<&str> = path.split('/').collect();
@@ -224,23 +460,182 @@ fn truncate_path(path: &str, max_len: usize) -> String {
       let end = parts.last().unwrap_or(&"");
       format!(".../{end}")
     } else {
-      format!("...{}", &path[path.len().saturating_sub(max_len - 3)..])
+      format!("...{}", last_chars(path, max_len.saturating_sub(3)))
     }
     .truncate(max_len)
   }
 }
 
+/// The last `n` characters of `s`, cut on a character boundary
+fn last_chars(s: &str, n: usize) -> &str {
+  let skip = s.chars().count().saturating_sub(n);
+  s.char_indices().nth(skip).map_or("", |(i, _)| &s[i..])
+}
+
 // Add String extension trait for truncation
 trait StringExt {
   fn truncate(&self, max_len: usize) -> String;
 }
 
 impl StringExt for String {
+  /// Cut to at most `max_len` characters, ending in "..." when shortened.
+  /// Counts characters rather than bytes so non-ASCII names aren't split.
   fn truncate(&self, max_len: usize) -> String {
-    if self.len() <= max_len {
+    if self.chars().count() <= max_len {
       self.clone()
     } else {
-      format!("{}...", &self[..max_len.saturating_sub(3)])
+      let kept: String = self.chars().take(max_len.saturating_sub(3)).collect();
+      format!("{kept}...")
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use ratatui::backend::TestBackend;
+
+  #[test]
+  fn test_problem_files_are_counted_once_each() {
+    let ui = ScanUI::new(true, Arc::new(AtomicBool::new(true)));
+    for path in ["a.env", "b.env", "a.env", "a.env"] {
+      ui.problem(path);
+    }
+    assert_eq!(
+      ui.state.lock().problem_files,
+      [("a.env".to_string(), 3), ("b.env".to_string(), 1)]
+    );
+  }
+
+  #[test]
+  fn test_plain_progress() {
+    let ui = ScanUI::new(true, Arc::new(AtomicBool::new(true)));
+    ui.start(20);
+    assert!(!ui.state.lock().interactive);
+    assert!(ui.render_thread.lock().is_none());
+    assert!(
+      !TUI_ACTIVE.load(Ordering::SeqCst),
+      "the terminal is untouched"
+    );
+
+    let lines: Vec<_> = (0..20)
+      .filter_map(|i| {
+        ui.complete(&format!("{i}.env"));
+        ui.state.lock().plain_progress()
+      })
+      .collect();
+    let expected: Vec<_> = (2..=20)
+      .step_by(2)
+      .map(|done| format!("Scanned {done}/20 files"))
+      .collect();
+    assert_eq!(lines, expected);
+  }
+
+  /// The screen's text, a line per row
+  fn screen(terminal: &Terminal<TestBackend>) -> Vec<String> {
+    let buffer = terminal.backend().buffer();
+    buffer
+      .content()
+      .chunks(buffer.area.width as usize)
+      .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+      .collect()
+  }
+
+  #[test]
+  fn test_render_thread_draws_updates() {
+    let ui = ScanUI::new(true, Arc::new(AtomicBool::new(true)));
+    let mut terminal = Terminal::new(TestBackend::new(80, 12)).unwrap();
+    std::thread::scope(|scope| {
+      let render = scope.spawn(|| {
+        ScanUI::render_loop(&mut terminal, &ui.state, &ui.done, &ui.running);
+      });
+
+      // Updates only change the state, the render thread draws them
+      ui.start(2);
+      ui.problem("a.env");
+      ui.complete("a.env");
+      ui.update("b.env", "Checking patterns", 0.5);
+      ui.done.store(true, Ordering::SeqCst);
+      render.join().unwrap();
+    });
+
+    let screen = screen(&terminal).join("\n");
+    assert!(screen.contains("Files with potential secrets:"), "{screen}");
+    assert!(screen.contains("● a.env"), "{screen}");
+    assert!(screen.contains("b.env"), "{screen}");
+    assert!(screen.contains("Checking patterns"), "{screen}");
+    assert!(screen.contains("Progress: 1/2"), "{screen}");
+  }
+
+  #[test]
+  fn test_inline_keeps_the_last_frame() {
+    let ui = ScanUI::new(true, Arc::new(AtomicBool::new(true)));
+    ui.start(1);
+    ui.problem("a.env");
+    ui.complete("a.env");
+    ui.done.store(true, Ordering::SeqCst);
+
+    let viewport = Viewport::Inline(INLINE_HEIGHT);
+    let mut terminal = Terminal::with_options(
+      TestBackend::new(80, 24),
+      TerminalOptions { viewport },
+    )
+    .unwrap();
+    ScanUI::render_loop(&mut terminal, &ui.state, &ui.done, &ui.running);
+
+    // The frame is drawn below the cursor and left on screen, with the
+    // cursor moved past it for whatever is printed next
+    let screen = screen(&terminal);
+    let height = INLINE_HEIGHT as usize;
+    assert!(screen[..height].concat().contains("● a.env"));
+    assert!(screen[height..].iter().all(|row| row.trim().is_empty()));
+    assert_eq!(
+      terminal.get_cursor_position().unwrap(),
+      (0, INLINE_HEIGHT - 1).into()
+    );
+  }
+
+  #[test]
+  fn test_progress_title() {
+    let now = Instant::now();
+    let mut state = State {
+      total_files: 15,
+      processed_files: 5,
+      started: Some(now - Duration::from_secs(70)),
+      // 5 files in the last 10 seconds is 2 seconds a file
+      recent_completions: (1..=5)
+        .map(|i| now - Duration::from_secs(i * 2 - 1))
+        .collect(),
+      ..State::default()
+    };
+    assert_eq!(
+      state.progress_title(now),
+      "Progress: 5/15 · 1:10 elapsed · ETA 0:20"
+    );
+
+    // Too early to estimate
+    state.started = Some(now - Duration::from_millis(300));
+    assert_eq!(
+      state.progress_title(now),
+      "Progress: 5/15 · 0:00 elapsed · ETA --"
+    );
+
+    assert_eq!(format_duration(Duration::from_secs(3725)), "1:02:05");
+  }
+
+  #[test]
+  fn test_truncate_path_multibyte() {
+    let path = "données/配置/秘密の鍵.env";
+    assert_eq!(truncate_path(path, 30), path);
+    assert_eq!(truncate_path(path, 12), ".../秘密の鍵.env");
+    assert_eq!(truncate_path(path, 10), ".../秘密の...");
+
+    // Two components are cut from the front, on a character boundary
+    assert_eq!(truncate_path("ディレクトリ/ファイル名", 8), "...ファイル名");
+    assert_eq!(
+      "ü".repeat(10).truncate(5).chars().count(),
+      5,
+      "truncation counts characters"
+    );
+  }
+}
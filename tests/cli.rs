@@ -0,0 +1,137 @@
+//! Runs the `ssq` binary for what only `main` decides: exit codes, and the
+//! messages printed around a scan's results
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tempfile::TempDir;
+
+const CONFIG: &str = r"
+patterns:
+  low-token:
+    regex: 'LOW_TOKEN=([A-Z0-9]{8})'
+    severity: low
+  medium-token:
+    regex: 'MEDIUM_TOKEN=([A-Z0-9]{8})'
+    severity: medium
+  high-token:
+    regex: 'HIGH_TOKEN=([A-Z0-9]{8})'
+    severity: high
+  critical-token:
+    regex: 'CRITICAL_TOKEN=([A-Z0-9]{8})'
+    severity: critical
+";
+
+/// A directory holding `ssq.yml`, with a pattern per severity
+fn project() -> std::io::Result<TempDir> {
+  let temp = TempDir::new()?;
+  std::fs::write(temp.path().join("ssq.yml"), CONFIG)?;
+  Ok(temp)
+}
+
+/// `ssq` run in `dir` with its `ssq.yml`, and no base config
+fn ssq(dir: &Path) -> Command {
+  let mut command = colored_ssq(dir);
+  command.arg("--no-color");
+  command
+}
+
+/// `ssq` as [`ssq`] runs it, but with colors forced on even though its
+/// output is piped
+fn colored_ssq(dir: &Path) -> Command {
+  let mut command = Command::new(env!("CARGO_BIN_EXE_ssq"));
+  command
+    .current_dir(dir)
+    .env("SSQ_CONFIG_DIR", dir.join("no-base-config"))
+    .env("CLICOLOR_FORCE", "1")
+    .env_remove("NO_COLOR")
+    .args(["--config", "ssq.yml"]);
+  command
+}
+
+#[test]
+fn test_timeout_reports_partial_results() -> std::io::Result<()> {
+  let temp = project()?;
+  let mut child = ssq(temp.path())
+    .args(["--stdin", "--timeout", "1", "--format", "json"])
+    .args(["--fail-on", "low"])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()?;
+
+  // Hold the input open past the timeout, so the scan can't finish first
+  let mut stdin = child.stdin.take().expect("stdin is piped");
+  stdin.write_all(b"HIGH_TOKEN=ABCD1234\n")?;
+  std::thread::sleep(Duration::from_millis(1500));
+  drop(stdin);
+  let output = child.wait_with_output()?;
+
+  // The scan stopped before any pattern ran, so nothing fails the run
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert_eq!(output.status.code(), Some(0), "{stderr}");
+  assert!(stderr.contains("Scan timed out after 1s, results are partial"));
+  let results: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+  assert_eq!(results, serde_json::json!([]));
+
+  Ok(())
+}
+
+#[test]
+fn test_fail_on_thresholds() -> std::io::Result<()> {
+  let temp = project()?;
+  let levels = ["low", "medium", "high", "critical"];
+  for level in levels {
+    std::fs::write(
+      temp.path().join(format!("{level}.env")),
+      format!("{}_TOKEN=ABCD1234\n", level.to_uppercase()),
+    )?;
+  }
+
+  for (threshold_rank, threshold) in levels.iter().enumerate() {
+    for (found_rank, found) in levels.iter().enumerate() {
+      let output = ssq(temp.path())
+        .args(["--format", "json", "--fail-on", threshold])
+        .arg(format!("{found}.env"))
+        .output()?;
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      let context = format!("{found} match, --fail-on {threshold}: {stderr}");
+      if found_rank >= threshold_rank {
+        assert_eq!(output.status.code(), Some(1), "{context}");
+        assert!(
+          stderr.contains(&format!(
+            "1 potential secrets at {} or above",
+            threshold.to_uppercase()
+          )),
+          "{context}"
+        );
+      } else {
+        assert_eq!(output.status.code(), Some(0), "{context}");
+      }
+    }
+  }
+
+  Ok(())
+}
+
+#[test]
+fn test_no_color() -> std::io::Result<()> {
+  let temp = project()?;
+  std::fs::write(temp.path().join("app.env"), "HIGH_TOKEN=ABCD1234\n")?;
+  let has_color = |command: &mut Command| -> std::io::Result<bool> {
+    let output = command
+      .args(["--fail-on", "critical", "app.env"])
+      .output()?;
+    assert_eq!(output.status.code(), Some(0));
+    Ok(output.stdout.windows(2).any(|pair| pair == b"\x1b["))
+  };
+
+  assert!(has_color(&mut colored_ssq(temp.path()))?);
+  assert!(!has_color(colored_ssq(temp.path()).arg("--no-color"))?);
+  assert!(!has_color(colored_ssq(temp.path()).env("NO_COLOR", "1"))?);
+  // An empty NO_COLOR doesn't count, per no-color.org
+  assert!(has_color(colored_ssq(temp.path()).env("NO_COLOR", ""))?);
+
+  Ok(())
+}